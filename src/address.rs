@@ -1,38 +1,375 @@
-use std::fmt::Display;
+//! Ethereum addresses and their mixed-case checksums.
+//! https://eips.ethereum.org/EIPS/eip-55
+//!
+//! Some chains like RSK include the chain id in the checksum, as in EIP-1191, so an address
+//! checksummed for one chain doesn't validate on another.
+//! https://eips.ethereum.org/EIPS/eip-1191
 
-use num_bigint::{BigInt, BigUint};
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Address(BigUint);
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-impl From<BigUint> for Address {
-    fn from(value: BigUint) -> Self {
-        Self(value)
-    }
+use crate::{ecdsa::Point, keccak::keccak256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// addresses are exactly 40 hex digits long, without the 0x prefix
+    InvalidLength(usize),
+    InvalidHex,
+    /// the address has both upper and lower case letters, but they don't match the checksum
+    ChecksumMismatch,
 }
 
-impl From<BigInt> for Address {
-    fn from(value: BigInt) -> Self {
-        let (_, value) = value.into_parts();
-        Self(value)
+impl Display for AddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressError::InvalidLength(len) => write!(f, "invalid address length {}", len),
+            AddressError::InvalidHex => write!(f, "address is not valid hex"),
+            AddressError::ChecksumMismatch => write!(f, "address checksum doesn't match"),
+        }
     }
 }
 
-impl From<Address> for String {
-    fn from(value: Address) -> Self {
-        // TODO: implement checksum
-        let mut bytes = value.0.to_bytes_le();
+impl std::error::Error for AddressError {}
+
+/// a 20 byte account address, ordered and hashed by its big endian bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address([u8; 20]);
+
+impl Address {
+    /// 0x0000000000000000000000000000000000000000, e.g. the recipient of contract creations
+    pub const ZERO: Self = Self([0; 20]);
+
+    pub fn from_bytes(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    /// parses an address from hex, with or without the 0x prefix
+    ///
+    /// all lower or all upper case input carries no checksum and is accepted as is,
+    /// mixed case input has to match the EIP-55 checksum
+    pub fn from_hex(hex: &str) -> Result<Self, AddressError> {
+        Self::parse(hex, None)
+    }
 
-        while bytes.len() < 20 {
-            bytes.push(0);
+    /// like `from_hex`, but mixed case input has to match the EIP-1191 checksum for `chain_id`
+    pub fn from_hex_with_chain_id(hex: &str, chain_id: u64) -> Result<Self, AddressError> {
+        Self::parse(hex, Some(chain_id))
+    }
+
+    fn parse(hex: &str, chain_id: Option<u64>) -> Result<Self, AddressError> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        if hex.len() != 40 {
+            return Err(AddressError::InvalidLength(hex.len()));
+        }
+
+        let bytes: [u8; 20] = hex::decode(hex)
+            .map_err(|_| AddressError::InvalidHex)?
+            .try_into()
+            .map_err(|_| AddressError::InvalidHex)?;
+
+        let has_lower = hex.bytes().any(|c| c.is_ascii_lowercase());
+        let has_upper = hex.bytes().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && checksum(&bytes, chain_id)[2..] != *hex {
+            return Err(AddressError::ChecksumMismatch);
         }
 
-        format!("0x{}", hex::encode(bytes.into_iter().rev().collect::<Vec<_>>()))
+        Ok(Self(bytes))
+    }
+
+    /// the EIP-55 mixed-case hex encoding with the 0x prefix
+    pub fn to_checksum(self) -> String {
+        checksum(&self.to_bytes(), None)
+    }
+
+    /// the EIP-1191 mixed-case hex encoding for `chain_id`, with the 0x prefix
+    pub fn to_checksum_with_chain_id(self, chain_id: u64) -> String {
+        checksum(&self.to_bytes(), Some(chain_id))
+    }
+
+    /// the address as 20 big endian bytes
+    pub fn to_bytes(self) -> [u8; 20] {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Address> for [u8; 20] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+/// the last 20 bytes of the keccak256 hash of x || y
+impl From<Point> for Address {
+    fn from(point: Point) -> Self {
+        let mut coordinates = [0u8; 64];
+        coordinates[..32].copy_from_slice(&point.x.to_bytes_be());
+        coordinates[32..].copy_from_slice(&point.y.to_bytes_be());
+
+        let hash = keccak256(&coordinates);
+        Self(hash[12..].try_into().unwrap())
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// parses with or without the 0x prefix and checks the EIP-55 checksum of mixed case input
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+/// serialized as the checksummed hex string
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<Address> for String {
+    fn from(value: Address) -> Self {
+        value.to_checksum()
     }
 }
 
 impl Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", String::from(self.clone()))
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+/// hex digit i is upper case if nibble i of the hash of the lower case address is at least 8,
+/// EIP-1191 hashes the chain id in decimal followed by the 0x prefixed address instead
+fn checksum(bytes: &[u8; 20], chain_id: Option<u64>) -> String {
+    let hex = hex::encode(bytes);
+    let hash = match chain_id {
+        Some(chain_id) => keccak256(format!("{}0x{}", chain_id, hex).as_bytes()),
+        None => keccak256(hex.as_bytes()),
+    };
+
+    let mut result = String::with_capacity(42);
+    result.push_str("0x");
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 - i % 2 * 4)) & 0xf;
+        if nibble >= 8 {
+            result.push(c.to_ascii_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eip55_vectors() {
+        // https://eips.ethereum.org/EIPS/eip-55#test-cases
+        for expected in [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x27b1fdb04752bbc536007a920d24acb045561c26",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = Address::from_hex(expected).unwrap();
+            assert_eq!(address.to_checksum(), expected);
+            assert_eq!(address.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn eip1191_vectors() {
+        // https://eips.ethereum.org/EIPS/eip-1191#test-cases
+        let vectors = [
+            (
+                30,
+                [
+                    "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD",
+                    "0xFb6916095cA1Df60bb79ce92cE3EA74c37c5d359",
+                    "0xDBF03B407c01E7CD3cBea99509D93F8Dddc8C6FB",
+                    "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB",
+                ],
+            ),
+            (
+                31,
+                [
+                    "0x5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd",
+                    "0xFb6916095CA1dF60bb79CE92ce3Ea74C37c5D359",
+                    "0xdbF03B407C01E7cd3cbEa99509D93f8dDDc8C6fB",
+                    "0xd1220a0CF47c7B9Be7A2E6Ba89f429762E7b9adB",
+                ],
+            ),
+        ];
+
+        for (chain_id, addresses) in vectors {
+            for expected in addresses {
+                let address = Address::from_hex_with_chain_id(expected, chain_id).unwrap();
+                assert_eq!(address.to_checksum_with_chain_id(chain_id), expected);
+                assert_eq!(
+                    Address::from_hex(expected),
+                    Err(AddressError::ChecksumMismatch)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn from_hex_checksum() {
+        let address = Address::from_hex("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+
+        // no prefix, no checksum
+        assert_eq!(
+            Address::from_hex("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            Ok(address)
+        );
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+            Ok(address)
+        );
+        assert_eq!(
+            Address::from_hex("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED"),
+            Ok(address)
+        );
+
+        // one letter with the wrong case
+        assert_eq!(
+            Address::from_hex("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+            Err(AddressError::ChecksumMismatch)
+        );
+        assert_eq!(
+            Address::from_hex_with_chain_id("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", 30),
+            Err(AddressError::ChecksumMismatch)
+        );
+    }
+
+    #[test]
+    fn from_hex_errors() {
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"),
+            Err(AddressError::InvalidLength(38))
+        );
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed00"),
+            Err(AddressError::InvalidLength(42))
+        );
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg"),
+            Err(AddressError::InvalidHex)
+        );
+        // a non-ascii character that makes the string 40 bytes long
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea\u{e9}"),
+            Err(AddressError::InvalidHex)
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse(),
+            Ok(address)
+        );
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<Address>(),
+            Err(AddressError::ChecksumMismatch)
+        );
+        assert_eq!(
+            address.as_ref(),
+            hex::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap()
+        );
+    }
+
+    #[test]
+    fn zero_address() {
+        assert!(Address::ZERO.is_zero());
+        assert_eq!(Address::default(), Address::ZERO);
+        assert_eq!(
+            Address::ZERO.to_string(),
+            "0x0000000000000000000000000000000000000000"
+        );
+        assert!(!Address::from([1; 20]).is_zero());
+    }
+
+    #[test]
+    fn ordered_by_bytes() {
+        let mut low = [0u8; 20];
+        low[19] = 0xff;
+        let mut high = [0u8; 20];
+        high[0] = 1;
+
+        assert!(Address::from(low) < Address::from(high));
+        assert!(Address::ZERO < Address::from(low));
+
+        let addresses: std::collections::HashSet<_> =
+            [Address::ZERO, Address::from(low), Address::ZERO].into();
+        assert_eq!(addresses.len(), 2);
+    }
+
+    #[test]
+    fn from_point() {
+        // the generator, whose private key is 1
+        let address = Address::from(crate::ecdsa::Point::from_hex(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        ));
+
+        assert_eq!(
+            address.to_string(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+
+    #[test]
+    fn serde_hex() {
+        let address = Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let json = serde_json::to_string(&address).unwrap();
+
+        assert_eq!(json, "\"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\"");
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+        assert_eq!(
+            serde_json::from_str::<Address>("\"5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\"")
+                .unwrap(),
+            address
+        );
+        assert!(
+            serde_json::from_str::<Address>("\"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD\"")
+                .is_err()
+        );
+        assert!(serde_json::from_str::<Address>("1").is_err());
     }
 }
@@ -0,0 +1,783 @@
+//! Hierarchical deterministic keys as described in BIP-32.
+//! https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+//!
+//! An extended key is a key together with a 32 byte chain code. The child with index i is
+//! derived from HMAC-SHA512(chain code, data), where the data is the parent public key and i,
+//! or for hardened children (i >= 2^31) the parent private key and i. The left half of the
+//! HMAC is added to the parent key and the right half is the child's chain code. Wallets use
+//! the BIP-44 path m/44'/60'/0'/0/n for the n-th Ethereum account.
+
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use hmac::{Hmac, Mac};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroize;
+
+use crate::{
+    address::Address,
+    ecdsa::{Curve, PrivateKey, PublicKey, Scalar, Secp256k1},
+};
+
+type HmacSha512 = Hmac<Sha512>;
+
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+/// version, depth, parent fingerprint, child number, chain code and key
+const SERIALIZED_LENGTH: usize = 4 + 1 + 4 + 4 + 32 + 33;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bip32Error {
+    /// seeds are 16 to 64 bytes long
+    InvalidSeedLength(usize),
+    /// the derived key is invalid, which happens with a probability of about 2^-127
+    InvalidChild,
+    /// hardened children can only be derived from private keys
+    HardenedFromPublic,
+    /// keys can be at most 255 levels deep
+    DepthExceeded,
+    InvalidPath,
+    /// not valid base58, wrong checksum or wrong length
+    InvalidEncoding,
+    /// the version isn't mainnet xprv or xpub
+    InvalidVersion,
+    /// the key or the depth, parent fingerprint and child number don't make sense together
+    InvalidKey,
+}
+
+impl Display for Bip32Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bip32Error::InvalidSeedLength(len) => write!(f, "invalid seed length {}", len),
+            Bip32Error::InvalidChild => write!(f, "derived key is invalid"),
+            Bip32Error::HardenedFromPublic => {
+                write!(f, "can't derive a hardened child from a public key")
+            }
+            Bip32Error::DepthExceeded => write!(f, "maximum depth exceeded"),
+            Bip32Error::InvalidPath => write!(f, "invalid derivation path"),
+            Bip32Error::InvalidEncoding => write!(f, "invalid extended key encoding"),
+            Bip32Error::InvalidVersion => write!(f, "unsupported extended key version"),
+            Bip32Error::InvalidKey => write!(f, "invalid extended key"),
+        }
+    }
+}
+
+impl std::error::Error for Bip32Error {}
+
+/// the index of a child, hardened indices have the top bit set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChildNumber(u32);
+
+impl ChildNumber {
+    pub const HARDENED: u32 = 1 << 31;
+
+    /// `None` if the index is 2^31 or larger
+    pub fn normal(index: u32) -> Option<Self> {
+        (index < Self::HARDENED).then_some(Self(index))
+    }
+
+    /// `None` if the index is 2^31 or larger
+    pub fn hardened(index: u32) -> Option<Self> {
+        (index < Self::HARDENED).then_some(Self(index | Self::HARDENED))
+    }
+
+    pub fn is_hardened(self) -> bool {
+        self.0 & Self::HARDENED != 0
+    }
+
+    /// the index without the hardened bit
+    pub fn index(self) -> u32 {
+        self.0 & !Self::HARDENED
+    }
+}
+
+impl From<u32> for ChildNumber {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ChildNumber> for u32 {
+    fn from(value: ChildNumber) -> Self {
+        value.0
+    }
+}
+
+impl Display for ChildNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.index())?;
+        if self.is_hardened() {
+            write!(f, "'")?;
+        }
+        Ok(())
+    }
+}
+
+/// a path like m/44'/60'/0'/0/0, hardened indices are marked with ' or h
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+impl DerivationPath {
+    /// the standard path of the n-th Ethereum account, m/44'/60'/0'/0/n
+    pub fn ethereum(account: u32) -> Option<Self> {
+        Some(Self(vec![
+            ChildNumber::hardened(44)?,
+            ChildNumber::hardened(60)?,
+            ChildNumber::hardened(0)?,
+            ChildNumber::normal(0)?,
+            ChildNumber::normal(account)?,
+        ]))
+    }
+
+    pub fn children(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Bip32Error;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let mut parts = path.split('/');
+        if parts.next() != Some("m") {
+            return Err(Bip32Error::InvalidPath);
+        }
+
+        parts
+            .map(|part| {
+                let (index, hardened) = match part
+                    .strip_suffix('\'')
+                    .or_else(|| part.strip_suffix('h'))
+                    .or_else(|| part.strip_suffix('H'))
+                {
+                    Some(index) => (index, true),
+                    None => (part, false),
+                };
+
+                // `parse` would also accept a leading +
+                if index.is_empty() || !index.bytes().all(|c| c.is_ascii_digit()) {
+                    return Err(Bip32Error::InvalidPath);
+                }
+                let index = index.parse().map_err(|_| Bip32Error::InvalidPath)?;
+
+                if hardened {
+                    ChildNumber::hardened(index)
+                } else {
+                    ChildNumber::normal(index)
+                }
+                .ok_or(Bip32Error::InvalidPath)
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+/// a private key with a chain code, the chain code is wiped from memory when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedPrivateKey {
+    key: PrivateKey,
+    chain_code: [u8; 32],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+}
+
+impl ExtendedPrivateKey {
+    /// the master key of a seed, e.g. one from a BIP-39 mnemonic
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Bip32Error> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(Bip32Error::InvalidSeedLength(seed.len()));
+        }
+
+        let (key, chain_code) = hmac_sha512(b"Bitcoin seed", &[seed]);
+        // the key is invalid with a probability of about 2^-127, the seed can't be used then
+        let key = PrivateKey::from_bytes(&key).map_err(|_| Bip32Error::InvalidChild)?;
+
+        Ok(Self {
+            key,
+            chain_code,
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: ChildNumber(0),
+        })
+    }
+
+    /// derives a single child, hardened or not
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self, Bip32Error> {
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::DepthExceeded)?;
+        let index = u32::from(child_number).to_be_bytes();
+
+        let (mut tweak, chain_code) = if child_number.is_hardened() {
+            hmac_sha512(&self.chain_code, &[&[0], self.key.as_bytes(), &index])
+        } else {
+            let public_key = self.key.public_key().to_bytes_compressed();
+            hmac_sha512(&self.chain_code, &[&public_key, &index])
+        };
+
+        // k_i = IL + k_par, invalid if IL >= O or k_i = 0
+        let key = Scalar::from_bytes_be(&tweak)
+            .map(|tweak| tweak + self.key.scalar())
+            .ok_or(Bip32Error::InvalidChild)
+            .and_then(|key| {
+                PrivateKey::from_bytes(&key.to_bytes_be()).map_err(|_| Bip32Error::InvalidChild)
+            });
+        tweak.zeroize();
+
+        Ok(Self {
+            key: key?,
+            chain_code,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number,
+        })
+    }
+
+    /// derives every child on the path in turn, starting from this key
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+        path.children()
+            .iter()
+            .try_fold(self.clone(), |key, child| key.derive_child(*child))
+    }
+
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.key
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    pub fn address(&self) -> Address {
+        self.public_key().address()
+    }
+
+    /// the extended public key, which can only derive the non-hardened children
+    pub fn extended_public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            key: self.public_key(),
+            chain_code: self.chain_code,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+        }
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// the first 4 bytes of the hash160 of the public key, identifies the parent of a child
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key())
+    }
+
+    /// the base58check "xprv..." encoding
+    pub fn to_xprv(&self) -> String {
+        let mut key = [0u8; 33];
+        key[1..].copy_from_slice(self.key.as_bytes());
+
+        let result = serialize(
+            XPRV_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        );
+        key.zeroize();
+        result
+    }
+
+    pub fn from_xprv(xprv: &str) -> Result<Self, Bip32Error> {
+        let mut bytes = deserialize(xprv, XPRV_VERSION)?;
+
+        let key = if bytes[45] == 0 {
+            PrivateKey::from_bytes(&bytes[46..]).map_err(|_| Bip32Error::InvalidKey)
+        } else {
+            Err(Bip32Error::InvalidKey)
+        };
+        let result = key.map(|key| Self {
+            key,
+            chain_code: bytes[13..45].try_into().unwrap(),
+            depth: bytes[4],
+            parent_fingerprint: bytes[5..9].try_into().unwrap(),
+            child_number: ChildNumber(u32::from_be_bytes(bytes[9..13].try_into().unwrap())),
+        });
+        bytes.zeroize();
+
+        result
+    }
+}
+
+impl Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // like `PrivateKey`, never print the key or the chain code
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &hex::encode(self.parent_fingerprint))
+            .field("child_number", &self.child_number)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+    }
+}
+
+/// a public key with a chain code, for deriving the non-hardened children's public keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    key: PublicKey,
+    chain_code: [u8; 32],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+}
+
+impl ExtendedPublicKey {
+    /// derives a single non-hardened child
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self, Bip32Error> {
+        if child_number.is_hardened() {
+            return Err(Bip32Error::HardenedFromPublic);
+        }
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::DepthExceeded)?;
+        let index = u32::from(child_number).to_be_bytes();
+
+        let (tweak, chain_code) =
+            hmac_sha512(&self.chain_code, &[&self.key.to_bytes_compressed(), &index]);
+
+        let key = add_tweak(&self.key, &tweak)?;
+
+        Ok(Self {
+            key,
+            chain_code,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number,
+        })
+    }
+
+    /// derives every child on the path in turn, fails if any of them is hardened
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+        path.children()
+            .iter()
+            .try_fold(self.clone(), |key, child| key.derive_child(*child))
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    pub fn address(&self) -> Address {
+        self.key.address()
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.key)
+    }
+
+    /// the base58check "xpub..." encoding
+    pub fn to_xpub(&self) -> String {
+        serialize(
+            XPUB_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.key.to_bytes_compressed(),
+        )
+    }
+
+    pub fn from_xpub(xpub: &str) -> Result<Self, Bip32Error> {
+        let bytes = deserialize(xpub, XPUB_VERSION)?;
+
+        // only compressed keys, `from_bytes` would accept a single 0x00 as infinity
+        if bytes[45] != 2 && bytes[45] != 3 {
+            return Err(Bip32Error::InvalidKey);
+        }
+
+        Ok(Self {
+            key: PublicKey::from_bytes(&bytes[45..]).map_err(|_| Bip32Error::InvalidKey)?,
+            chain_code: bytes[13..45].try_into().unwrap(),
+            depth: bytes[4],
+            parent_fingerprint: bytes[5..9].try_into().unwrap(),
+            child_number: ChildNumber(u32::from_be_bytes(bytes[9..13].try_into().unwrap())),
+        })
+    }
+}
+
+/// HMAC-SHA512 of the concatenated data, split into its two halves
+/// K_i = IL * G + K_par, invalid if IL >= O or K_i is infinity,
+/// IL = 0 is valid like in the private derivation and gives K_par
+fn add_tweak(parent: &PublicKey, tweak: &[u8; 32]) -> Result<PublicKey, Bip32Error> {
+    let tweak = Scalar::from_bytes_be(tweak).ok_or(Bip32Error::InvalidChild)?;
+    let point = Secp256k1::generator_table()
+        .mul_secret(&tweak)
+        .add(parent.point());
+
+    PublicKey::from_point(point).map_err(|_| Bip32Error::InvalidChild)
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for data in data {
+        mac.update(data);
+    }
+    let mut result: [u8; 64] = mac.finalize().into_bytes().into();
+
+    let halves = (
+        result[..32].try_into().unwrap(),
+        result[32..].try_into().unwrap(),
+    );
+    result.zeroize();
+    halves
+}
+
+fn fingerprint(public_key: &PublicKey) -> [u8; 4] {
+    let hash = Ripemd160::digest(Sha256::digest(public_key.to_bytes_compressed()));
+    hash[..4].try_into().unwrap()
+}
+
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: &[u8; 32],
+    key: &[u8; 33],
+) -> String {
+    let mut bytes = Vec::with_capacity(SERIALIZED_LENGTH);
+    bytes.extend_from_slice(&version);
+    bytes.push(depth);
+    bytes.extend_from_slice(&parent_fingerprint);
+    bytes.extend_from_slice(&u32::from(child_number).to_be_bytes());
+    bytes.extend_from_slice(chain_code);
+    bytes.extend_from_slice(key);
+
+    let result = bs58::encode(&bytes).with_check().into_string();
+    bytes.zeroize();
+    result
+}
+
+/// decodes and checks the version and the depth, parent fingerprint and child number
+fn deserialize(encoded: &str, version: [u8; 4]) -> Result<Vec<u8>, Bip32Error> {
+    let mut bytes = bs58::decode(encoded)
+        .with_check(None)
+        .into_vec()
+        .map_err(|_| Bip32Error::InvalidEncoding)?;
+
+    let result = if bytes.len() != SERIALIZED_LENGTH {
+        Err(Bip32Error::InvalidEncoding)
+    } else if bytes[..4] != version {
+        Err(Bip32Error::InvalidVersion)
+    } else if bytes[4] == 0 && bytes[5..13] != [0; 8] {
+        // a master key has no parent
+        Err(Bip32Error::InvalidKey)
+    } else {
+        return Ok(bytes);
+    };
+
+    bytes.zeroize();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn master(seed: &str) -> ExtendedPrivateKey {
+        ExtendedPrivateKey::from_seed(&hex::decode(seed).unwrap()).unwrap()
+    }
+
+    fn path(path: &str) -> DerivationPath {
+        path.parse().unwrap()
+    }
+
+    /// checks the xprv and xpub of a key, and that they decode to the same key
+    fn check(key: &ExtendedPrivateKey, xprv: &str, xpub: &str) {
+        assert_eq!(key.to_xprv(), xprv);
+        assert_eq!(key.extended_public_key().to_xpub(), xpub);
+
+        assert_eq!(ExtendedPrivateKey::from_xprv(xprv).as_ref(), Ok(key));
+        assert_eq!(
+            ExtendedPublicKey::from_xpub(xpub),
+            Ok(key.extended_public_key())
+        );
+    }
+
+    #[test]
+    fn test_vector_1() {
+        let master = master("000102030405060708090a0b0c0d0e0f");
+
+        check(
+            &master,
+            "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+            "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+        );
+        check(
+            &master.derive_path(&path("m/0H")).unwrap(),
+            "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+            "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+        );
+        check(
+            &master.derive_path(&path("m/0H/1")).unwrap(),
+            "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+            "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+        );
+        check(
+            &master.derive_path(&path("m/0H/1/2H/2/1000000000")).unwrap(),
+            "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+            "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+        );
+    }
+
+    #[test]
+    fn test_vector_2() {
+        let master = master("fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542");
+
+        check(
+            &master,
+            "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+            "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+        );
+        check(
+            &master.derive_path(&path("m/0")).unwrap(),
+            "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+            "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+        );
+    }
+
+    #[test]
+    fn ethereum_accounts() {
+        // the seed of "test test test test test test test test test test test junk",
+        // the default accounts of Hardhat and Foundry, imported the same way by MetaMask
+        let master = master("9dfc3c64c2f8bede1533b6a79f8570e5943e0b8fd1cf77107adf7b72cef42185d564a3aee24cab43f80e3c4538087d70fc824eabbad596a23c97b6ee8322ccc0");
+
+        let first = master.derive_path(&path("m/44'/60'/0'/0/0")).unwrap();
+        assert_eq!(
+            first.private_key().to_hex(),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        assert_eq!(
+            String::from(first.address()),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        let second = master
+            .derive_path(&DerivationPath::ethereum(1).unwrap())
+            .unwrap();
+        assert_eq!(
+            String::from(second.address()),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+
+    #[test]
+    fn public_derivation_matches_private() {
+        let master = master("000102030405060708090a0b0c0d0e0f");
+        let account = master.derive_path(&path("m/44'/60'/0'/0")).unwrap();
+        let account_public = account.extended_public_key();
+
+        for index in [0, 1, 7, ChildNumber::HARDENED - 1] {
+            let child = ChildNumber::normal(index).unwrap();
+            assert_eq!(
+                account_public.derive_child(child),
+                Ok(account.derive_child(child).unwrap().extended_public_key())
+            );
+        }
+
+        assert_eq!(
+            account_public.derive_child(ChildNumber::hardened(0).unwrap()),
+            Err(Bip32Error::HardenedFromPublic)
+        );
+        assert_eq!(
+            master
+                .extended_public_key()
+                .derive_path(&path("m/0/1/2"))
+                .unwrap()
+                .address(),
+            master.derive_path(&path("m/0/1/2")).unwrap().address()
+        );
+    }
+
+    #[test]
+    fn public_tweak_edge_cases() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let parent = PrivateKey::from_bytes(&one).unwrap().public_key();
+        let order: [u8; 32] =
+            hex::decode("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let mut minus_one = order;
+        minus_one[31] -= 1;
+
+        // IL = 0 leaves the key unchanged, IL >= O or a child at infinity are invalid
+        assert_eq!(add_tweak(&parent, &[0; 32]), Ok(parent.clone()));
+        assert_eq!(add_tweak(&parent, &order), Err(Bip32Error::InvalidChild));
+        assert_eq!(
+            add_tweak(&parent, &[0xff; 32]),
+            Err(Bip32Error::InvalidChild)
+        );
+        assert_eq!(
+            add_tweak(&parent, &minus_one),
+            Err(Bip32Error::InvalidChild)
+        );
+        assert_eq!(
+            add_tweak(&parent, &one),
+            Ok(PrivateKey::from_bytes(&[&[0u8; 31][..], &[2]].concat())
+                .unwrap()
+                .public_key())
+        );
+    }
+
+    #[test]
+    fn derivation_paths() {
+        let ethereum = path("m/44'/60'/0'/0/0");
+        assert_eq!(Some(ethereum.clone()), DerivationPath::ethereum(0));
+        assert_eq!(ethereum.to_string(), "m/44'/60'/0'/0/0");
+        assert_eq!(path("m/44h/60H/0'/0/0"), ethereum);
+        assert_eq!(path("m/2147483647'").to_string(), "m/2147483647'");
+        assert_eq!(path("m"), DerivationPath::default());
+
+        for invalid in [
+            "",
+            "/0",
+            "M/0",
+            "0/1",
+            "m/",
+            "m//0",
+            "m/0/",
+            "m/+1",
+            "m/-1",
+            "m/1''",
+            "m/x",
+            "m/2147483648",
+            "m/2147483648'",
+        ] {
+            assert_eq!(
+                invalid.parse::<DerivationPath>(),
+                Err(Bip32Error::InvalidPath),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_extended_keys() {
+        // from BIP-32 test vector 5
+        for (xkey, error) in [
+            // pubkey version / prvkey mismatch
+            ("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFGTQQD3dC4H2D5GBj7vWvSQaaBv5cxi9gafk7NF3pnBju6dwKvH", Bip32Error::InvalidKey),
+            // invalid prvkey prefix 04
+            ("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFAzHGBP2UuGCqWLTAPLcMtD9y5gkZ6Eq3Rjuahrv17fEQ3Qen6J", Bip32Error::InvalidKey),
+            // zero depth with non-zero parent fingerprint
+            ("xprv9s2SPatNQ9Vc6GTbVMFPFo7jsaZySyzk7L8n2uqKXJen3KUmvQNTuLh3fhZMBoG3G4ZW1N2kZuHEPY53qmbZzCHshoQnNf4GvELZfqTUrcv", Bip32Error::InvalidKey),
+            // zero depth with non-zero index
+            ("xpub661no6RGEX3uJkY4bNnPcw4URcQTrSibUZ4NqJEw5eBkv7ovTwgiT91XX27VbEXGENhYRCf7hyEbWrR3FewATdCEebj6znwMfQkhRYHRLpJ", Bip32Error::InvalidKey),
+            // private key 0 not in 1..n-1
+            ("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzF93Y5wvzdUayhgkkFoicQZcP3y52uPPxFnfoLZB21Teqt1VvEHx", Bip32Error::InvalidKey),
+            // private key n not in 1..n-1
+            ("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFAzHGBP2UuGCqWLTAPLcMtD5SDKr24z3aiUvKr9bJpdrcLg1y3G", Bip32Error::InvalidKey),
+            // invalid pubkey 020000000000000000000000000000000000000000000000000000000000000007
+            ("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6Q5JXayek4PRsn35jii4veMimro1xefsM58PgBMrvdYre8QyULY", Bip32Error::InvalidKey),
+            // invalid checksum
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHL", Bip32Error::InvalidEncoding),
+        ] {
+            let result = if xkey.starts_with("xprv") {
+                ExtendedPrivateKey::from_xprv(xkey).map(|_| ())
+            } else {
+                ExtendedPublicKey::from_xpub(xkey).map(|_| ())
+            };
+            assert_eq!(result, Err(error), "{}", xkey);
+        }
+
+        let xpub = master("000102030405060708090a0b0c0d0e0f")
+            .extended_public_key()
+            .to_xpub();
+        assert_eq!(
+            ExtendedPrivateKey::from_xprv(&xpub),
+            Err(Bip32Error::InvalidVersion)
+        );
+        assert_eq!(
+            ExtendedPublicKey::from_xpub(&xpub[..xpub.len() - 1]),
+            Err(Bip32Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn seed_length_and_depth() {
+        assert_eq!(
+            ExtendedPrivateKey::from_seed(&[0; 15]),
+            Err(Bip32Error::InvalidSeedLength(15))
+        );
+        assert_eq!(
+            ExtendedPrivateKey::from_seed(&[0; 65]),
+            Err(Bip32Error::InvalidSeedLength(65))
+        );
+
+        let mut key = master("000102030405060708090a0b0c0d0e0f");
+        for _ in 0..255 {
+            key = key.derive_child(ChildNumber::normal(0).unwrap()).unwrap();
+        }
+        assert_eq!(key.depth(), 255);
+        assert_eq!(
+            key.derive_child(ChildNumber::normal(0).unwrap()),
+            Err(Bip32Error::DepthExceeded)
+        );
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let key = master("000102030405060708090a0b0c0d0e0f");
+        let debug = format!("{:?}", key);
+
+        assert!(!debug.contains(&key.private_key().to_hex()));
+        assert!(!debug.contains(&hex::encode(key.chain_code())));
+    }
+}
@@ -0,0 +1,371 @@
+//! Mnemonic seed phrases as described in BIP-39, with the English word list.
+//! https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+//!
+//! 128 to 256 bits of entropy get a checksum of one bit per 32 bits of entropy, the first bits
+//! of its sha256. Every 11 bits of entropy || checksum pick one of the 2048 words, giving 12 to
+//! 24 words. The seed for BIP-32 is PBKDF2-HMAC-SHA512 of the mnemonic, with 2048 iterations
+//! and "mnemonic" || passphrase as the salt, so every passphrase gives a different wallet.
+
+use std::fmt::Display;
+
+use hmac::Hmac;
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+
+use crate::bip32::{Bip32Error, ExtendedPrivateKey};
+
+const SEED_ITERATIONS: u32 = 2048;
+
+/// the English word list, sorted so words can be found with a binary search
+static WORDS: Lazy<Vec<&'static str>> = Lazy::new(|| include_str!("english.txt").lines().collect());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// entropy is 16, 20, 24, 28 or 32 bytes long
+    InvalidEntropyLength(usize),
+    /// mnemonics have 12, 15, 18, 21 or 24 words
+    InvalidWordCount(usize),
+    /// the word at this position isn't in the word list
+    UnknownWord(usize),
+    InvalidChecksum,
+}
+
+impl Display for MnemonicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MnemonicError::InvalidEntropyLength(len) => {
+                write!(f, "invalid entropy length {}", len)
+            }
+            MnemonicError::InvalidWordCount(count) => {
+                write!(f, "invalid number of words {}", count)
+            }
+            MnemonicError::UnknownWord(position) => {
+                write!(f, "word {} is not in the word list", position + 1)
+            }
+            MnemonicError::InvalidChecksum => write!(f, "invalid mnemonic checksum"),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+/// a valid mnemonic, wiped from memory when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+    /// the words separated by single spaces
+    phrase: String,
+}
+
+impl Mnemonic {
+    /// generates a new mnemonic from the operating system's CSPRNG
+    pub fn random(word_count: usize) -> Result<Self, MnemonicError> {
+        if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+            return Err(MnemonicError::InvalidWordCount(word_count));
+        }
+
+        // every 3 words hold 32 bits of entropy and 1 bit of checksum
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        OsRng.fill_bytes(&mut entropy);
+
+        let mnemonic = Self::from_entropy(&entropy);
+        entropy.zeroize();
+        mnemonic
+    }
+
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, MnemonicError> {
+        if !(16..=32).contains(&entropy.len()) || entropy.len() % 4 != 0 {
+            return Err(MnemonicError::InvalidEntropyLength(entropy.len()));
+        }
+
+        // at most 8 bits of checksum, the rest of the byte is ignored
+        let checksum = Sha256::digest(entropy)[0];
+
+        let mut words = Vec::with_capacity(entropy.len() * 3 / 4);
+        let mut bits = 0u32;
+        let mut bit_count = 0;
+        for byte in entropy.iter().chain([&checksum]) {
+            bits = bits << 8 | *byte as u32;
+            bit_count += 8;
+
+            while bit_count >= 11 {
+                bit_count -= 11;
+                words.push(WORDS[(bits >> bit_count) as usize & 0x7ff]);
+            }
+        }
+        bits.zeroize();
+
+        Ok(Self {
+            entropy: entropy.to_vec(),
+            phrase: words.join(" "),
+        })
+    }
+
+    /// parses and validates a mnemonic, the words can be separated by any whitespace
+    pub fn from_phrase(phrase: &str) -> Result<Self, MnemonicError> {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if !(12..=24).contains(&words.len()) || words.len() % 3 != 0 {
+            return Err(MnemonicError::InvalidWordCount(words.len()));
+        }
+
+        let mut entropy = Vec::with_capacity(words.len() * 11 / 8 + 1);
+        let mut bits = 0u32;
+        let mut bit_count = 0;
+        for (position, word) in words.iter().enumerate() {
+            let index = WORDS
+                .binary_search(word)
+                .map_err(|_| MnemonicError::UnknownWord(position))?;
+
+            bits = bits << 11 | index as u32;
+            bit_count += 11;
+
+            while bit_count >= 8 {
+                bit_count -= 8;
+                entropy.push((bits >> bit_count) as u8);
+            }
+        }
+        bits.zeroize();
+
+        // the entropy comes first, recomputing the mnemonic from it checks the checksum bits
+        entropy.truncate(words.len() / 3 * 4);
+        let mnemonic = Self::from_entropy(&entropy);
+        entropy.zeroize();
+
+        match mnemonic {
+            Ok(mnemonic) if mnemonic.words().eq(words) => Ok(mnemonic),
+            _ => Err(MnemonicError::InvalidChecksum),
+        }
+    }
+
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.phrase.split(' ')
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// the 64 byte BIP-32 seed, the passphrase can be empty
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        // the passphrase is NFKD normalized, the English words don't change
+        let mut salt = format!("mnemonic{}", passphrase).nfkd().collect::<String>();
+
+        let mut seed = [0u8; 64];
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(
+            self.phrase.as_bytes(),
+            salt.as_bytes(),
+            SEED_ITERATIONS,
+            &mut seed,
+        );
+        salt.zeroize();
+
+        seed
+    }
+
+    /// the BIP-32 master key of the wallet, derive accounts with `DerivationPath::ethereum`
+    pub fn to_master_key(&self, passphrase: &str) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let mut seed = self.to_seed(passphrase);
+        let master_key = ExtendedPrivateKey::from_seed(&seed);
+        seed.zeroize();
+
+        master_key
+    }
+}
+
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the words, e.g. in panic messages
+        write!(f, "Mnemonic({} words)", self.words().count())
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.entropy.zeroize();
+        self.phrase.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bip32::DerivationPath;
+
+    use super::*;
+
+    // from the Trezor reference implementation, all with the passphrase "TREZOR"
+    const VECTORS: [(&str, &str, &str); 10] = [
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "80808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+        ),
+        (
+            "000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+            "035895f2f481b1b0f01fcf8c289c794660b289981a78f8106447707fdd9666ca06da5a9a565181599b79f53b844d8a71dd9f439c52a3d7b3e8a79c906ac845fa",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+            "dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028",
+        ),
+        (
+            "6610b25967cdcca9d59875f5cb50b0ea75433311869e930b",
+            "gravity machine north sort system female filter attitude volume fold club stay feature office ecology stable narrow fog",
+            "628c3827a8823298ee685db84f55caa34b5cc195a778e52d45f59bcf75aba68e4d7590e101dc414bc1bbd5737666fbbef35d1f1903953b66624f910feef245ac",
+        ),
+        (
+            "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+            "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+            "01f5bced59dec48e362f2c45b5de68b9fd6c92c6634f44d6d40aab69056506f0e35524a518034ddc1192e1dacd32c1ed3eaa3c3b131c88ed8e7e54c49a5d0998",
+        ),
+    ];
+
+    #[test]
+    fn word_list() {
+        assert_eq!(WORDS.len(), 2048);
+        assert_eq!(WORDS[0], "abandon");
+        assert_eq!(WORDS[2047], "zoo");
+        assert!(WORDS.windows(2).all(|words| words[0] < words[1]));
+    }
+
+    #[test]
+    fn trezor_vectors() {
+        for (entropy, phrase, seed) in VECTORS {
+            let mnemonic = Mnemonic::from_entropy(&hex::decode(entropy).unwrap()).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+
+            let parsed = Mnemonic::from_phrase(phrase).unwrap();
+            assert_eq!(hex::encode(parsed.entropy()), entropy);
+            assert_eq!(hex::encode(parsed.to_seed("TREZOR")), seed);
+        }
+    }
+
+    #[test]
+    fn ethereum_wallet() {
+        let mnemonic =
+            Mnemonic::from_phrase("test test test test test test test test test test test junk")
+                .unwrap();
+        let master_key = mnemonic.to_master_key("").unwrap();
+
+        let account = master_key
+            .derive_path(&DerivationPath::ethereum(0).unwrap())
+            .unwrap();
+        assert_eq!(
+            String::from(account.address()),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        // a passphrase gives a different wallet
+        let other = mnemonic
+            .to_master_key("passphrase")
+            .unwrap()
+            .derive_path(&DerivationPath::ethereum(0).unwrap())
+            .unwrap();
+        assert_ne!(other.address(), account.address());
+    }
+
+    #[test]
+    fn passphrase_is_normalized() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+
+        // composed and decomposed forms of the same text give the same seed
+        let composed = mnemonic.to_seed("Ünïcöde パスワード");
+        let decomposed =
+            mnemonic.to_seed("U\u{308}ni\u{308}co\u{308}de ハ\u{309a}スワート\u{3099}");
+        assert_eq!(composed, decomposed);
+        assert_eq!(
+            hex::encode(composed),
+            "98acf04c8440047781a006633c4b953d4b29ced629b905e4eb3cf84b055ad12898b951d0fbc96183b31b2ae906e03f07b73dc786900f66762a9826b66fa26264"
+        );
+    }
+
+    #[test]
+    fn random_mnemonics() {
+        for word_count in [12, 15, 18, 21, 24] {
+            let mnemonic = Mnemonic::random(word_count).unwrap();
+
+            assert_eq!(mnemonic.words().count(), word_count);
+            assert_eq!(Mnemonic::from_phrase(mnemonic.phrase()), Ok(mnemonic));
+        }
+
+        assert_ne!(Mnemonic::random(12), Mnemonic::random(12));
+        assert_eq!(
+            Mnemonic::random(13),
+            Err(MnemonicError::InvalidWordCount(13))
+        );
+    }
+
+    #[test]
+    fn invalid_mnemonics() {
+        assert_eq!(
+            Mnemonic::from_entropy(&[0; 12]),
+            Err(MnemonicError::InvalidEntropyLength(12))
+        );
+        assert_eq!(
+            Mnemonic::from_entropy(&[0; 17]),
+            Err(MnemonicError::InvalidEntropyLength(17))
+        );
+
+        let phrase = VECTORS[1].1;
+        assert!(Mnemonic::from_phrase(&format!("  {}\n", phrase.replace(' ', "\t"))).is_ok());
+        assert_eq!(
+            Mnemonic::from_phrase(&phrase.replace("yellow", "year")),
+            Err(MnemonicError::InvalidChecksum)
+        );
+        assert_eq!(
+            Mnemonic::from_phrase(&phrase.replace("wave", "waves")),
+            Err(MnemonicError::UnknownWord(4))
+        );
+        assert_eq!(
+            Mnemonic::from_phrase(&phrase.replace("legal", "Legal")),
+            Err(MnemonicError::UnknownWord(0))
+        );
+        assert_eq!(
+            Mnemonic::from_phrase("abandon abandon abandon"),
+            Err(MnemonicError::InvalidWordCount(3))
+        );
+        assert_eq!(
+            Mnemonic::from_phrase(""),
+            Err(MnemonicError::InvalidWordCount(0))
+        );
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+        assert_eq!(format!("{:?}", mnemonic), "Mnemonic(12 words)");
+    }
+}
@@ -0,0 +1,274 @@
+//! Batch signature verification and public key recovery.
+//!
+//! Validating a block means recovering the sender of every transaction in it. Done one
+//! signature at a time, a good part of the work goes into modular inversions, so the batch
+//! functions share them between all signatures with Montgomery's trick: inverting every
+//! r (or s) and converting every result back to affine coordinates takes one inversion each.
+//!
+//! Invalid signatures never panic, they give an error (or `false`) at their position in the
+//! output. The `_parallel` variants additionally split the batch between the threads of the
+//! current rayon pool, which are started once and reused for every call. That's the global
+//! pool unless the caller runs them inside `ThreadPool::install`.
+
+use rayon::prelude::*;
+
+use crate::address::Address;
+
+use super::{
+    curve::{JacobianPoint, Secp256k1, G_TABLE},
+    field::{add_raw, lt_raw, Fp, PrimeField, Secp256k1Field, Secp256k1Scalar},
+    PublicKey, RecoveryError, Scalar, Signature,
+};
+
+/// the smallest chunk handed to a thread, batches up to this size run on the calling thread
+/// since sending them to the pool would cost more than the Montgomery's trick saves
+const MIN_CHUNK_SIZE: usize = 32;
+
+/// recovers the public key of every (signature, hash) pair, like `Signature::recover_public_key`
+pub fn recover_public_keys_batch(
+    items: &[(Signature, [u8; 32])],
+) -> Vec<Result<PublicKey, RecoveryError>> {
+    let mut r_invs: Vec<Scalar> = items.iter().map(|(signature, _)| signature.r).collect();
+    Scalar::batch_invert(&mut r_invs);
+
+    // failed items are converted as the point at infinity, their errors are kept aside
+    let mut errors = Vec::with_capacity(items.len());
+    let points: Vec<JacobianPoint<Secp256k1>> = items
+        .iter()
+        .zip(r_invs)
+        .map(|((signature, hash), r_inv)| {
+            let r_point = match signature.recovery_point() {
+                Ok(r_point) => r_point,
+                Err(error) => {
+                    errors.push(Some(error));
+                    return JacobianPoint::infinity();
+                }
+            };
+            errors.push(None);
+
+            let z = Scalar::from_bytes_be_reduced(hash);
+            let u1 = -z * r_inv;
+            let u2 = signature.s * r_inv;
+
+            G_TABLE.mul_add(&u1, &r_point, &u2)
+        })
+        .collect();
+
+    JacobianPoint::batch_to_affine(&points)
+        .into_iter()
+        .zip(errors)
+        .map(|(point, error)| match error {
+            Some(error) => Err(error),
+            None if point.is_infinity() => Err(RecoveryError::PointAtInfinity),
+            None => Ok(PublicKey::from_point_unchecked(point)),
+        })
+        .collect()
+}
+
+/// the batch version of `Signature::ecrecover`
+pub fn ecrecover_batch(items: &[(Signature, [u8; 32])]) -> Vec<Result<Address, RecoveryError>> {
+    recover_public_keys_batch(items)
+        .into_iter()
+        .map(|public_key| public_key.map(|public_key| public_key.address()))
+        .collect()
+}
+
+/// like `ecrecover_batch`, but spread over the threads of the current rayon pool
+pub fn ecrecover_batch_parallel(
+    items: &[(Signature, [u8; 32])],
+) -> Vec<Result<Address, RecoveryError>> {
+    parallel_chunks(items, ecrecover_batch)
+}
+
+/// the batch version of `Signature::verify`, checks every (signature, hash, public key)
+pub fn verify_batch(items: &[(Signature, [u8; 32], PublicKey)]) -> Vec<bool> {
+    let mut s_invs: Vec<Scalar> = items.iter().map(|(signature, _, _)| signature.s).collect();
+    Scalar::batch_invert(&mut s_invs);
+
+    items
+        .iter()
+        .zip(s_invs)
+        .map(|((signature, hash, public_key), w)| {
+            if signature.r.is_zero() || signature.s.is_zero() {
+                return false;
+            }
+            if signature.recovery_id().is_none() {
+                return false;
+            }
+
+            let z = Scalar::from_bytes_be_reduced(hash);
+            let u1 = z * w;
+            let u2 = signature.r * w;
+
+            let point = G_TABLE.mul_add(&u1, public_key.point(), &u2);
+
+            x_matches_r(&point, &signature.r)
+        })
+        .collect()
+}
+
+/// like `verify_batch`, but spread over the threads of the current rayon pool
+pub fn verify_batch_parallel(items: &[(Signature, [u8; 32], PublicKey)]) -> Vec<bool> {
+    parallel_chunks(items, verify_batch)
+}
+
+/// checks that the affine x coordinate of the point is r mod O,
+/// without an inversion to convert the point to affine coordinates
+fn x_matches_r(point: &JacobianPoint<Secp256k1>, r: &Scalar) -> bool {
+    if point.is_infinity() {
+        return false;
+    }
+
+    // x / z^2 = r  <=>  x = r * z^2
+    let z2 = point.z.square();
+    let r = r.to_raw();
+    if point.x == Fp::from_raw(r) * z2 {
+        return true;
+    }
+
+    // the x coordinate may also have been r + O, if that's still smaller than P
+    let (r_plus_o, carry) = add_raw(&r, &Secp256k1Scalar::MODULUS);
+    carry == 0
+        && lt_raw(&r_plus_o, &Secp256k1Field::MODULUS)
+        && point.x == Fp::from_raw(r_plus_o) * z2
+}
+
+/// runs `f` on one chunk of the items per thread of the current pool
+/// and concatenates the results in order
+fn parallel_chunks<T: Sync, R: Send>(items: &[T], f: fn(&[T]) -> Vec<R>) -> Vec<R> {
+    let threads = rayon::current_num_threads();
+    let chunk_size = ((items.len() + threads - 1) / threads).max(MIN_CHUNK_SIZE);
+
+    if items.len() <= chunk_size {
+        return f(items);
+    }
+
+    let chunks: Vec<Vec<R>> = items.par_chunks(chunk_size).map(f).collect();
+    chunks.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ecdsa::PrivateKey, keccak::keccak256};
+
+    use super::*;
+
+    fn signed_items(count: u8) -> Vec<(Signature, [u8; 32], PublicKey)> {
+        (0..count)
+            .map(|i| {
+                let private_key = PrivateKey::from_bytes(&keccak256(&[i, 1])).unwrap();
+                let hash = keccak256(&[i]);
+                let signature = Signature::create(&private_key, hash);
+
+                (signature, hash, private_key.public_key())
+            })
+            .collect()
+    }
+
+    fn recovery_items(items: &[(Signature, [u8; 32], PublicKey)]) -> Vec<(Signature, [u8; 32])> {
+        items
+            .iter()
+            .map(|(signature, hash, _)| (signature.clone(), *hash))
+            .collect()
+    }
+
+    #[test]
+    fn batch_recover_matches_single() {
+        let items = signed_items(20);
+        let recovered = recover_public_keys_batch(&recovery_items(&items));
+
+        for ((signature, hash, public_key), recovered) in items.iter().zip(recovered) {
+            assert_eq!(recovered.as_ref(), Ok(public_key));
+            assert_eq!(recovered, signature.recover_public_key(hash));
+        }
+    }
+
+    #[test]
+    fn batch_recover_reports_failures() {
+        let items = signed_items(4);
+        let mut batch = recovery_items(&items);
+
+        // invalid v
+        batch[0].0.v = 29 + 4;
+        // x = 5 is not on the curve
+        batch[1].0.r = Scalar::from_u64(5);
+        // r + O is larger than P, so recovery ids 2 and 3 can't be valid for this r
+        batch[2].0.v = 29;
+        batch.push((
+            Signature {
+                r: Scalar::ZERO,
+                s: Scalar::ONE,
+                v: 27,
+            },
+            [0u8; 32],
+        ));
+
+        let recovered = ecrecover_batch(&batch);
+
+        assert_eq!(
+            recovered[..3],
+            [
+                Err(RecoveryError::InvalidRecoveryId(33)),
+                Err(RecoveryError::NonResidue),
+                Err(RecoveryError::ROutOfRange)
+            ]
+        );
+        assert_eq!(recovered[3], Ok(items[3].2.address()));
+        assert_eq!(recovered[4], Err(RecoveryError::ROutOfRange));
+    }
+
+    #[test]
+    fn batch_verify() {
+        let mut items = signed_items(6);
+
+        // wrong hash, wrong key, zero s
+        items[0].1[0] ^= 1;
+        items[1].2 = items[2].2.clone();
+        items[3].0.s = Scalar::ZERO;
+
+        assert_eq!(
+            verify_batch(&items),
+            [false, false, true, false, true, true]
+        );
+
+        for (signature, hash, public_key) in items.iter() {
+            assert_eq!(
+                verify_batch(&[(signature.clone(), *hash, public_key.clone())]),
+                [signature.verify(hash, public_key)]
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let mut items = signed_items(100);
+        items[42].0.v = 0;
+        items[77].1[5] ^= 1;
+
+        let recovery = recovery_items(&items);
+        let expected = ecrecover_batch(&recovery);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        assert_eq!(
+            pool.install(|| ecrecover_batch_parallel(&recovery)),
+            expected
+        );
+        assert_eq!(ecrecover_batch_parallel(&recovery), expected);
+        assert_eq!(expected[42], Err(RecoveryError::InvalidRecoveryId(0)));
+
+        assert_eq!(
+            pool.install(|| verify_batch_parallel(&items)),
+            verify_batch(&items)
+        );
+    }
+
+    #[test]
+    fn empty_batches() {
+        assert!(ecrecover_batch(&[]).is_empty());
+        assert!(ecrecover_batch_parallel(&[]).is_empty());
+        assert!(verify_batch(&[]).is_empty());
+    }
+}
@@ -1,13 +1,87 @@
-/// implementation of secp256k1
-/// we only implement this curve since this is used in Ethereum
-use std::ops::Mul;
+/// short Weierstrass curves y^2 = x^3 + a * x + b over 256 bit prime fields
+/// the points are generic over the curve parameters, secp256k1 is the one used in Ethereum
+use std::{cmp::Ordering, fmt::Debug};
 
-use num_bigint::{BigInt, Sign};
+use num_bigint::BigInt;
 use once_cell::sync::Lazy;
 
-use super::number_theory::{mod_inverse, modulo};
+use super::field::{
+    adc, add_raw, mac, FieldElement, Fp, PrimeField, Scalar, Secp256k1Field, Secp256k1Scalar,
+};
 
-// secp256k1 is y^2 = x^3 + 7
+/// the parameters of a curve y^2 = x^3 + a * x + b with a generator of prime order
+pub trait Curve: Debug + Clone + Copy + PartialEq + Eq + 'static {
+    /// the coordinates of points are integers mod p
+    type Field: PrimeField;
+    /// scalars are integers mod the order of the generator
+    type Scalar: PrimeField;
+
+    fn a() -> CurveField<Self>;
+    fn b() -> CurveField<Self>;
+    fn generator() -> AffinePoint<Self>;
+
+    /// precomputed multiples of the generator, shared by all fixed-base multiplications
+    fn generator_table() -> &'static GeneratorTable<Self>;
+
+    /// the GLV endomorphism, for curves that have an efficiently computable one
+    fn endomorphism() -> Option<Endomorphism<Self>> {
+        None
+    }
+}
+
+/// an element of the field of the curve's coordinates
+pub type CurveField<C> = FieldElement<<C as Curve>::Field>;
+
+/// an element of the field of the curve's scalars
+pub type CurveScalar<C> = FieldElement<<C as Curve>::Scalar>;
+
+/// an endomorphism (x, y) -> (β * x, y) that is the same as multiplying by some λ
+/// see https://www.iacr.org/archive/crypto2001/21390189.pdf
+#[derive(Clone, Copy)]
+pub struct Endomorphism<C: Curve> {
+    /// a cube root of unity mod p
+    pub beta: CurveField<C>,
+    /// splits k into (k1, k2) with k = k1 + k2 * λ, where both halves are small in absolute value
+    pub decompose: fn(&CurveScalar<C>) -> SplitScalar<C>,
+}
+
+/// a scalar k split into (k1, k2) with k = k1 + k2 * λ
+pub type SplitScalar<C> = (CurveScalar<C>, CurveScalar<C>);
+
+/// secp256k1 is y^2 = x^3 + 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl Curve for Secp256k1 {
+    type Field = Secp256k1Field;
+    type Scalar = Secp256k1Scalar;
+
+    fn a() -> Fp {
+        Fp::ZERO
+    }
+
+    fn b() -> Fp {
+        *B
+    }
+
+    fn generator() -> Point {
+        *G
+    }
+
+    fn generator_table() -> &'static GeneratorTable<Self> {
+        &G_TABLE
+    }
+
+    fn endomorphism() -> Option<Endomorphism<Self>> {
+        Some(Endomorphism {
+            beta: *BETA,
+            decompose: glv_decompose,
+        })
+    }
+}
+
+/// a point on secp256k1
+pub type Point = AffinePoint<Secp256k1>;
 
 /// the curve is mod P
 pub static P: Lazy<BigInt> = Lazy::new(|| {
@@ -29,114 +103,766 @@ pub static O: Lazy<BigInt> = Lazy::new(|| {
     )
 });
 
+/// the constant term of the curve equation
+pub static B: Lazy<Fp> = Lazy::new(|| Fp::from_u64(7));
+
 /// the curve generator point
 pub static G: Lazy<Point> = Lazy::new(|| {
-    Point::new(
-        &BigInt::from_bytes_be(
-            Sign::Plus,
-            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
-                .unwrap()
-                .as_slice(),
-        ),
-        &BigInt::from_bytes_be(
-            Sign::Plus,
-            hex::decode("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8")
-                .unwrap()
-                .as_slice(),
-        ),
+    Point::from_hex(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
     )
 });
 
-#[derive(Debug, Clone)]
-pub struct Point {
-    pub x: BigInt,
-    pub y: BigInt,
+/// bits of the scalar handled by each row of the generator table
+const G_TABLE_WINDOW: usize = 8;
+
+/// precomputed multiples of the generator, used for all fixed-base multiplications
+/// building it takes a moment, so it's done lazily on first use
+pub static G_TABLE: Lazy<GeneratorTable<Secp256k1>> = Lazy::new(GeneratorTable::new);
+
+/// window size for the generator in `GeneratorTable::mul_add`, with w = 8 there are 64
+/// precomputed odd multiples, since they are shared by every call a wide window is cheap
+const G_WNAF_WINDOW: u32 = 8;
+
+/// row i holds j * 2^(8i) * G for j in 1..256, so multiplying G by a scalar takes
+/// a single mixed addition per byte of the scalar and no doublings at all
+///
+/// `odd_multiples` holds G, 3G, 5G, ... for wNAF, which `mul_add` interleaves with
+/// the digits of another point, `odd_multiples_lambda` holds the same points mapped
+/// through the endomorphism (and is empty if the curve has none)
+pub struct GeneratorTable<C: Curve> {
+    rows: Vec<Vec<AffinePoint<C>>>,
+    odd_multiples: Vec<AffinePoint<C>>,
+    odd_multiples_lambda: Vec<AffinePoint<C>>,
 }
 
-impl Point {
-    pub fn new(x: &BigInt, y: &BigInt) -> Self {
+impl<C: Curve> GeneratorTable<C> {
+    pub(crate) fn new() -> Self {
+        let row_len = (1 << G_TABLE_WINDOW) - 1;
+        let row_count = (256 + G_TABLE_WINDOW - 1) / G_TABLE_WINDOW;
+
+        let generator = C::generator();
+        let mut points = Vec::with_capacity(row_len * row_count);
+        let mut base = generator.to_jacobian();
+
+        for _ in 0..row_count {
+            let mut current = base;
+            for _ in 0..row_len {
+                points.push(current);
+                current = current.add(&base);
+            }
+            // current is now 2^8 * base
+            base = current;
+        }
+
+        let odd_multiples = generator.odd_multiples(G_WNAF_WINDOW);
+        points.extend_from_slice(&odd_multiples);
+
+        let mut points = JacobianPoint::batch_to_affine(&points);
+        let odd_multiples = points.split_off(row_len * row_count);
+        let odd_multiples_lambda = match C::endomorphism() {
+            Some(_) => odd_multiples
+                .iter()
+                .map(AffinePoint::endomorphism)
+                .collect(),
+            None => Vec::new(),
+        };
+        let rows = points.chunks(row_len).map(|row| row.to_vec()).collect();
+
         Self {
-            x: modulo(x, &P),
-            y: modulo(y, &P),
+            rows,
+            odd_multiples,
+            odd_multiples_lambda,
+        }
+    }
+
+    pub fn mul(&self, a: &CurveScalar<C>) -> AffinePoint<C> {
+        self.mul_jacobian(a).to_affine()
+    }
+
+    /// variable time, the bytes of the scalar pick the table entries directly and zero bytes
+    /// are skipped, so like `AffinePoint::mul` this is only meant for public scalars
+    pub fn mul_jacobian(&self, a: &CurveScalar<C>) -> JacobianPoint<C> {
+        let mut result = JacobianPoint::infinity();
+
+        // with a window of 8 bits every row corresponds to one byte of the scalar
+        for (row, byte) in self.rows.iter().zip(a.to_bytes_be().into_iter().rev()) {
+            if byte != 0 {
+                result = result.add_affine(&row[byte as usize - 1]);
+            }
         }
+
+        result
     }
 
+    /// the constant time version of `mul`, for private keys and nonces
+    pub fn mul_secret(&self, a: &CurveScalar<C>) -> AffinePoint<C> {
+        self.mul_secret_jacobian(a).to_affine()
+    }
+
+    /// the scalar is written with signed digits in [-127, 128], so only the first 128 entries
+    /// of each row are needed and a negative digit picks the inverse of an entry, to keep the
+    /// top digit in range a scalar of 2^255 or more is negated first and the result flipped
+    ///
+    /// all 128 entries are read for every digit and the right one is picked with
+    /// `conditional_select`, and every row costs one addition even if its digit is zero,
+    /// so neither the memory accesses nor the sequence of operations depend on the scalar
+    ///
+    /// the sum starts at G instead of infinity (and G is subtracted at the end) so the
+    /// additions stay clear of their infinity special case, like in `AffinePoint::mul_secret`
+    /// only the equal point cases are left, which a negligible fraction of scalars hits
+    pub fn mul_secret_jacobian(&self, a: &CurveScalar<C>) -> JacobianPoint<C> {
+        let half = 1 << (G_TABLE_WINDOW - 1);
+
+        // both orders are above 2^255, so O - a is below 2^255 if a isn't
+        let negate = a.to_bytes_be()[0] >> 7 == 1;
+        let k = CurveScalar::<C>::conditional_select(a, &-*a, negate);
+
+        let generator = C::generator();
+        let mut result = generator.to_jacobian();
+        let mut carry = 0;
+
+        for (row, byte) in self.rows.iter().zip(k.to_bytes_be().into_iter().rev()) {
+            // a digit above 128 becomes digit - 256 and carries into the next byte
+            let mut digit = byte as i32 + carry;
+            carry = ((half - digit) >> 31) & 1;
+            digit -= carry << G_TABLE_WINDOW;
+
+            let sign = digit >> 31;
+            let magnitude = ((digit ^ sign) - sign) as usize;
+
+            let mut entry = row[0];
+            for (i, point) in row[..half as usize].iter().enumerate().skip(1) {
+                entry = AffinePoint::conditional_select(&entry, point, i + 1 == magnitude);
+            }
+            entry = AffinePoint::conditional_select(&entry, &entry.inverse(), sign != 0);
+
+            let sum = result.add_affine(&entry);
+            result = JacobianPoint::conditional_select(&result, &sum, magnitude != 0);
+        }
+
+        let result = result.add_affine(&generator.inverse());
+        JacobianPoint::conditional_select(&result, &result.neg(), negate)
+    }
+
+    /// computes u1 * G + u2 * Q with Shamir's trick: the wNAF digits of both scalars are
+    /// processed together so both multiplications share a single chain of doublings
+    ///
+    /// if the curve has an endomorphism both scalars are also split with GLV, so there are
+    /// four scalars of about 128 bits and only half as many doublings
+    ///
+    /// variable time, like `AffinePoint::mul` this is only meant for public scalars
+    pub fn mul_add(
+        &self,
+        u1: &CurveScalar<C>,
+        q: &AffinePoint<C>,
+        u2: &CurveScalar<C>,
+    ) -> JacobianPoint<C> {
+        if q.is_infinity() {
+            return self.mul_jacobian(u1);
+        }
+
+        let q_table = q.odd_multiples(WNAF_WINDOW);
+
+        let endomorphism = match C::endomorphism() {
+            Some(endomorphism) => endomorphism,
+            None => {
+                return interleaved_wnaf(
+                    &[(wnaf(u1, G_WNAF_WINDOW), &self.odd_multiples)],
+                    &[(wnaf(u2, WNAF_WINDOW), &q_table)],
+                )
+            }
+        };
+
+        let (u1_1, u1_2) = (endomorphism.decompose)(u1);
+        let (u2_1, u2_2) = (endomorphism.decompose)(u2);
+
+        let q_table_lambda: Vec<JacobianPoint<C>> =
+            q_table.iter().map(JacobianPoint::endomorphism).collect();
+
+        interleaved_wnaf(
+            &[
+                (signed_wnaf(&u1_1, G_WNAF_WINDOW), &self.odd_multiples),
+                (
+                    signed_wnaf(&u1_2, G_WNAF_WINDOW),
+                    &self.odd_multiples_lambda,
+                ),
+            ],
+            &[
+                (signed_wnaf(&u2_1, WNAF_WINDOW), &q_table),
+                (signed_wnaf(&u2_2, WNAF_WINDOW), &q_table_lambda),
+            ],
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AffinePoint<C: Curve> {
+    pub x: CurveField<C>,
+    pub y: CurveField<C>,
+}
+
+impl<C: Curve> AffinePoint<C> {
+    pub fn new(x: CurveField<C>, y: CurveField<C>) -> Self {
+        Self { x, y }
+    }
+
+    /// panics if either coordinate isn't valid hex smaller than P
     pub fn from_hex(x: &str, y: &str) -> Self {
-        Point::new(
-            &BigInt::from_bytes_be(Sign::Plus, hex::decode(x).unwrap().as_slice()),
-            &BigInt::from_bytes_be(Sign::Plus, hex::decode(y).unwrap().as_slice()),
+        Self::new(
+            CurveField::<C>::from_hex(x).expect("invalid x coordinate"),
+            CurveField::<C>::from_hex(y).expect("invalid y coordinate"),
         )
     }
 
+    /// the point with the given x coordinate and parity of y, `None` if x^3 + a * x + b
+    /// has no square root, i.e. there is no point with that x coordinate
+    pub fn from_x(x: CurveField<C>, y_is_odd: bool) -> Option<Self> {
+        let y = (x.square() * x + C::a() * x + C::b()).sqrt()?;
+
+        // pick the root with the requested parity
+        let y = if y.is_odd() == y_is_odd { y } else { -y };
+        Some(Self::new(x, y))
+    }
+
     pub fn infinity() -> Self {
-        Point::new(&BigInt::default(), &BigInt::default())
+        Self::new(CurveField::<C>::ZERO, CurveField::<C>::ZERO)
     }
 
+    /// checks y^2 = x^3 + a * x + b (mod P)
+    /// the point at infinity has no affine coordinates, so it's not considered to be on the curve
     pub fn is_on_curve(&self) -> bool {
-        unimplemented!()
+        if self.is_infinity() {
+            return false;
+        }
+
+        self.y.square() == self.x.square() * self.x + C::a() * self.x + C::b()
+    }
+
+    /// (0, 0) stands for the point at infinity, it's not on any curve with b != 0
+    pub fn is_infinity(&self) -> bool {
+        self.x.is_zero() && self.y.is_zero()
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::new(self.x, -self.y)
+    }
+
+    /// returns `b` if `choice` is set and `a` otherwise, without branching on `choice`
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        Self::new(
+            CurveField::<C>::conditional_select(&a.x, &b.x, choice),
+            CurveField::<C>::conditional_select(&a.y, &b.y, choice),
+        )
+    }
+
+    pub fn to_jacobian(self) -> JacobianPoint<C> {
+        JacobianPoint::from_affine(&self)
     }
 
-    pub fn inverse(&self) -> Point {
-        Point::new(&self.x, &self.y.clone().mul(-1))
+    pub fn add(&self, q: &Self) -> Self {
+        self.to_jacobian().add_affine(q).to_affine()
     }
 
-    pub fn add(&self, q: &Point) -> Point {
-        if *self == Point::infinity() {
-            return q.clone();
+    /// variable time scalar multiplication, the running time depends on the scalar
+    /// so this must only be used with public values (e.g. signature verification)
+    /// use `mul_secret` for private keys and nonces
+    pub fn mul(&self, a: &CurveScalar<C>) -> Self {
+        self.mul_jacobian(a).to_affine()
+    }
+
+    /// scalar multiplication without the final conversion to affine coordinates,
+    /// useful when the result is only an intermediate value
+    ///
+    /// if the curve has an endomorphism the scalar is split with GLV into two halves of about
+    /// 128 bits, which are multiplied with P and λP at the same time, sharing the doublings
+    pub fn mul_jacobian(&self, a: &CurveScalar<C>) -> JacobianPoint<C> {
+        if self.is_infinity() {
+            return JacobianPoint::infinity();
         }
 
-        if *q == Point::infinity() {
-            return self.clone();
+        let table = self.odd_multiples(WNAF_WINDOW);
+
+        let endomorphism = match C::endomorphism() {
+            Some(endomorphism) => endomorphism,
+            None => return interleaved_wnaf(&[], &[(wnaf(a, WNAF_WINDOW), &table)]),
+        };
+
+        let (k1, k2) = (endomorphism.decompose)(a);
+        let table_lambda: Vec<JacobianPoint<C>> =
+            table.iter().map(JacobianPoint::endomorphism).collect();
+
+        interleaved_wnaf(
+            &[],
+            &[
+                (signed_wnaf(&k1, WNAF_WINDOW), &table),
+                (signed_wnaf(&k2, WNAF_WINDOW), &table_lambda),
+            ],
+        )
+    }
+
+    /// the endomorphism (x, y) -> (β * x, y), which is the same as multiplying by λ
+    /// panics if the curve doesn't have one
+    pub fn endomorphism(&self) -> Self {
+        let beta = C::endomorphism()
+            .expect("the curve has no endomorphism")
+            .beta;
+        Self::new(self.x * beta, self.y)
+    }
+
+    /// the odd multiples P, 3P, 5P, ..., (2^(w-1) - 1)P used by the wNAF digits
+    fn odd_multiples(&self, w: u32) -> Vec<JacobianPoint<C>> {
+        let double = self.to_jacobian().double();
+        let mut table = vec![self.to_jacobian()];
+        for i in 1..(1 << (w - 2)) {
+            let next = table[i - 1].add(&double);
+            table.push(next);
         }
 
-        if *self == self.inverse() {
-            return Point::infinity();
+        table
+    }
+}
+
+impl<C: Curve> AffinePoint<C> {
+    /// scalar multiplication for secret scalars (private keys, nonces)
+    ///
+    /// this is a Montgomery ladder over a fixed number of bits: every bit costs exactly one
+    /// addition and one doubling, and the bits only select which points are swapped
+    /// so the sequence of operations doesn't depend on the scalar
+    ///
+    /// the field arithmetic and the swaps don't branch on their inputs, only the special
+    /// cases in the addition formulas do (equal points, infinity), which the ladder only
+    /// hits for a negligible fraction of scalars
+    pub fn mul_secret(&self, a: &CurveScalar<C>) -> Self {
+        self.mul_secret_jacobian(a).to_affine()
+    }
+
+    pub fn mul_secret_jacobian(&self, a: &CurveScalar<C>) -> JacobianPoint<C> {
+        if self.is_infinity() {
+            return JacobianPoint::infinity();
         }
 
-        let lambda;
+        // add O or 2 * O to the scalar so that it always has exactly 257 bits,
+        // this way the ladder can start at 1 * P with the top bit already processed
+        // (this works for any order above 2^255)
+        let (once, carry) = add_raw(&a.to_raw(), &C::Scalar::MODULUS);
+        let (twice, _) = add_raw(&once, &C::Scalar::MODULUS);
 
-        if *self == *q {
-            // to avoid division by zero we have a special case for point doubling
-            let numerator = modulo(&(3 * self.x.pow(2)), &P);
-            let denominator = modulo(&(2 * self.y.clone()), &P);
-            lambda = modulo(&(numerator * mod_inverse(&denominator, &P)), &P);
-        } else {
-            // lambda = mpdmod_p() * mod_inverse(&(), &P);
-            let numerator = modulo(&(q.clone().y - self.clone().y), &P);
-            let denominator = modulo(&(q.clone().x - self.clone().x), &P);
-            lambda = modulo(&(numerator * mod_inverse(&denominator, &P)), &P);
+        let mask = carry.wrapping_sub(1);
+        let mut k = [0u64; 4];
+        for i in 0..4 {
+            k[i] = once[i] ^ (mask & (once[i] ^ twice[i]));
         }
 
-        let xr = lambda.clone().pow(2) - q.clone().x - self.clone().x;
-        let yr = lambda * (self.clone().x - xr.clone()) - self.clone().y;
+        let mut r0 = self.to_jacobian();
+        let mut r1 = r0.double();
+
+        for i in (0..256).rev() {
+            let bit = (k[i / 64] >> (i % 64)) & 1 == 1;
 
-        Point::new(&xr, &yr)
+            JacobianPoint::conditional_swap(&mut r0, &mut r1, bit);
+            r1 = r0.add(&r1);
+            r0 = r0.double();
+            JacobianPoint::conditional_swap(&mut r0, &mut r1, bit);
+        }
+
+        r0
     }
+}
 
-    pub fn mul(&self, a: &BigInt) -> Point {
-        if a.sign() == Sign::Minus {
-            let inv = self.inverse();
-            inv.mul(&(a * -1))
-        } else if *a == BigInt::from(0) {
-            Point::infinity()
-        } else if *a == BigInt::from(1) {
-            self.clone()
-        } else if a.clone() % 2 == BigInt::from(1) {
-            self.add(&self.mul(&(a - 1)))
+/// window size for wNAF scalar multiplication, with w = 5 we precompute 8 odd multiples
+const WNAF_WINDOW: u32 = 5;
+
+/// width-w non-adjacent form of a scalar, least significant digit first
+/// every non-zero digit is odd and lies in (-2^(w-1), 2^(w-1)), and any w consecutive
+/// digits contain at most one non-zero digit
+fn wnaf<M: PrimeField>(scalar: &FieldElement<M>, w: u32) -> Vec<i32> {
+    let raw = scalar.to_raw();
+    // one spare limb, since subtracting a negative digit can carry past 256 bits
+    let mut k = [raw[0], raw[1], raw[2], raw[3], 0];
+    let window_mask = (1u64 << w) - 1;
+    let half = 1 << (w - 1);
+
+    let mut digits = Vec::with_capacity(257);
+
+    while k != [0; 5] {
+        let digit = if k[0] & 1 == 1 {
+            let mut digit = (k[0] & window_mask) as i32;
+            if digit >= half {
+                digit -= 1 << w;
+            }
+
+            if digit > 0 {
+                // the digit is exactly the low bits of k, so there is no borrow
+                k[0] -= digit as u64;
+            } else {
+                let mut carry = (-digit) as u64;
+                for limb in k.iter_mut() {
+                    let (sum, overflow) = limb.overflowing_add(carry);
+                    *limb = sum;
+                    carry = overflow as u64;
+                }
+            }
+
+            digit
         } else {
-            let double = self.add(self);
-            double.mul(&(a / 2))
+            0
+        };
+
+        digits.push(digit);
+
+        for i in 0..4 {
+            k[i] = (k[i] >> 1) | (k[i + 1] << 63);
+        }
+        k[4] >>= 1;
+    }
+
+    digits
+}
+
+/// sums the multiples of several points given by their wNAF digits, the digits of all
+/// terms are processed together so they share a single chain of doublings
+/// each table holds the odd multiples of one point, either in affine or Jacobian coordinates
+fn interleaved_wnaf<C: Curve>(
+    affine_terms: &[(Vec<i32>, &Vec<AffinePoint<C>>)],
+    jacobian_terms: &[(Vec<i32>, &Vec<JacobianPoint<C>>)],
+) -> JacobianPoint<C> {
+    let len = affine_terms
+        .iter()
+        .map(|(digits, _)| digits.len())
+        .chain(jacobian_terms.iter().map(|(digits, _)| digits.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut result = JacobianPoint::infinity();
+
+    for i in (0..len).rev() {
+        result = result.double();
+
+        for (digits, table) in affine_terms {
+            if let Some(&digit) = digits.get(i) {
+                match digit.cmp(&0) {
+                    Ordering::Greater => result = result.add_affine(&table[(digit / 2) as usize]),
+                    Ordering::Less => {
+                        result = result.add_affine(&table[(-digit / 2) as usize].inverse())
+                    }
+                    Ordering::Equal => {}
+                }
+            }
+        }
+
+        for (digits, table) in jacobian_terms {
+            if let Some(&digit) = digits.get(i) {
+                match digit.cmp(&0) {
+                    Ordering::Greater => result = result.add(&table[(digit / 2) as usize]),
+                    Ordering::Less => result = result.add(&table[(-digit / 2) as usize].neg()),
+                    Ordering::Equal => {}
+                }
+            }
+        }
+    }
+
+    result
+}
+
+// GLV endomorphism, see https://www.iacr.org/archive/crypto2001/21390189.pdf
+// secp256k1 has a cube root of unity β mod P and λ mod O such that (β * x, y) = λ * (x, y)
+// any scalar k can be written as k1 + k2 * λ with k1 and k2 only about 128 bits long,
+// the constants are the same as in libsecp256k1
+
+/// cube root of unity mod P
+static BETA: Lazy<Fp> = Lazy::new(|| {
+    Fp::from_hex("7ae96a2b657c07106e64479eac3434e99cf0497512f58995c1396c28719501ee").unwrap()
+});
+
+/// cube root of unity mod O, multiplying by it is the same as applying the endomorphism
+static LAMBDA: Lazy<Scalar> = Lazy::new(|| {
+    Scalar::from_hex("5363ad4cc05c30e0a5261c028812645a122e22ea20816678df02967c1b23bd72").unwrap()
+});
+
+/// -b1 and -b2 from the short lattice basis (a1, b1), (a2, b2) with a + b * λ = 0 (mod O)
+static MINUS_B1: Lazy<Scalar> =
+    Lazy::new(|| Scalar::from_hex("e4437ed6010e88286f547fa90abfe4c3").unwrap());
+static MINUS_B2: Lazy<Scalar> = Lazy::new(|| {
+    Scalar::from_hex("fffffffffffffffffffffffffffffffe8a280ac50774346dd765cda83db1562c").unwrap()
+});
+
+/// round(2^384 * b2 / O) and round(2^384 * -b1 / O), least significant limb first
+const G1: [u64; 4] = [
+    0xe893209a45dbb031,
+    0x3daa8a1471e8ca7f,
+    0xe86c90e49284eb15,
+    0x3086d221a7d46bcd,
+];
+const G2: [u64; 4] = [
+    0x1571b4ae8ac47f71,
+    0x221208ac9df506c6,
+    0x6f547fa90abfe4c4,
+    0xe4437ed6010e8828,
+];
+
+/// splits k into (k1, k2) with k = k1 + k2 * λ (mod O), where k1 and k2 are either
+/// below 2^128 or above O - 2^128, i.e. small negative numbers
+fn glv_decompose(k: &Scalar) -> (Scalar, Scalar) {
+    let raw = k.to_raw();
+
+    // c1 = round(b2 * k / O), c2 = round(-b1 * k / O)
+    let c1 = Scalar::from_raw(mul_shift_384(&raw, &G1));
+    let c2 = Scalar::from_raw(mul_shift_384(&raw, &G2));
+
+    let k2 = c1 * *MINUS_B1 + c2 * *MINUS_B2;
+    let k1 = *k - k2 * *LAMBDA;
+
+    (k1, k2)
+}
+
+/// round(a * b / 2^384) for 256 bit numbers, the result has at most 129 bits
+fn mul_shift_384(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut product = [0u64; 8];
+
+    for (i, a_i) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, b_j) in b.iter().enumerate() {
+            (product[i + j], carry) = mac(product[i + j], *a_i, *b_j, carry);
         }
+        product[i + 4] = carry;
+    }
+
+    // round up if bit 383 is set
+    let round = product[5] >> 63;
+    let (low, carry) = adc(product[6], round, 0);
+    let (high, carry) = adc(product[7], 0, carry);
+
+    [low, high, carry, 0]
+}
+
+/// wNAF of a scalar that is small in absolute value, negative scalars (close to O)
+/// get the digits of their negation with flipped signs
+fn signed_wnaf<M: PrimeField>(k: &FieldElement<M>, w: u32) -> Vec<i32> {
+    let negated = -*k;
+
+    if negated < *k {
+        wnaf(&negated, w).into_iter().map(|digit| -digit).collect()
+    } else {
+        wnaf(k, w)
     }
 }
 
-impl PartialEq for Point {
+/// a point in Jacobian coordinates, (x, y, z) represents the affine point (x / z^2, y / z^3)
+/// this lets us add and double points without a modular inversion for every operation,
+/// we only need a single inversion when converting back to affine coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct JacobianPoint<C: Curve> {
+    pub x: CurveField<C>,
+    pub y: CurveField<C>,
+    pub z: CurveField<C>,
+}
+
+impl<C: Curve> JacobianPoint<C> {
+    pub fn infinity() -> Self {
+        Self {
+            x: CurveField::<C>::ONE,
+            y: CurveField::<C>::ONE,
+            z: CurveField::<C>::ZERO,
+        }
+    }
+
+    pub fn from_affine(point: &AffinePoint<C>) -> Self {
+        if point.is_infinity() {
+            return Self::infinity();
+        }
+
+        Self {
+            x: point.x,
+            y: point.y,
+            z: CurveField::<C>::ONE,
+        }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    pub fn to_affine(self) -> AffinePoint<C> {
+        if self.is_infinity() {
+            return AffinePoint::infinity();
+        }
+
+        let z_inv = self.z.invert();
+        let z_inv2 = z_inv.square();
+        let z_inv3 = z_inv2 * z_inv;
+
+        AffinePoint::new(self.x * z_inv2, self.y * z_inv3)
+    }
+
+    /// returns `b` if `choice` is set and `a` otherwise, without branching on `choice`
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        Self {
+            x: CurveField::<C>::conditional_select(&a.x, &b.x, choice),
+            y: CurveField::<C>::conditional_select(&a.y, &b.y, choice),
+            z: CurveField::<C>::conditional_select(&a.z, &b.z, choice),
+        }
+    }
+
+    /// swaps `a` and `b` if `swap` is set, without branching on it
+    pub fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        CurveField::<C>::conditional_swap(&mut a.x, &mut b.x, swap);
+        CurveField::<C>::conditional_swap(&mut a.y, &mut b.y, swap);
+        CurveField::<C>::conditional_swap(&mut a.z, &mut b.z, swap);
+    }
+
+    /// converts many points to affine coordinates with a single modular inversion,
+    /// using Montgomery's trick
+    pub fn batch_to_affine(points: &[Self]) -> Vec<AffinePoint<C>> {
+        // points at infinity have z = 0, which batch_invert skips
+        let mut z_invs: Vec<CurveField<C>> = points.iter().map(|point| point.z).collect();
+        CurveField::<C>::batch_invert(&mut z_invs);
+
+        points
+            .iter()
+            .zip(z_invs)
+            .map(|(point, z_inv)| {
+                if point.is_infinity() {
+                    return AffinePoint::infinity();
+                }
+
+                let z_inv2 = z_inv.square();
+                let z_inv3 = z_inv2 * z_inv;
+                AffinePoint::new(point.x * z_inv2, point.y * z_inv3)
+            })
+            .collect()
+    }
+
+    pub fn neg(&self) -> Self {
+        Self {
+            x: self.x,
+            y: -self.y,
+            z: self.z,
+        }
+    }
+
+    /// the endomorphism (x, y) -> (β * x, y), in Jacobian coordinates only x is scaled by β
+    /// panics if the curve doesn't have one
+    pub fn endomorphism(&self) -> Self {
+        let beta = C::endomorphism()
+            .expect("the curve has no endomorphism")
+            .beta;
+
+        Self {
+            x: self.x * beta,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    /// "dbl-2007-bl" from https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html,
+    /// which is "dbl-2009-l" from the a = 0 page with one extra term for a != 0
+    pub fn double(&self) -> Self {
+        if self.is_infinity() || self.y.is_zero() {
+            return Self::infinity();
+        }
+
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = ((self.x + b).square() - a - c).double();
+        let mut e = a.double() + a;
+        if !C::a().is_zero() {
+            e = e + C::a() * self.z.square().square();
+        }
+        let f = e.square();
+
+        let x = f - d.double();
+        let y = e * (d - x) - c.double().double().double();
+        let z = (self.y * self.z).double();
+
+        Self { x, y, z }
+    }
+
+    /// "add-1998-cmo-2" from https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html
+    pub fn add(&self, q: &Self) -> Self {
+        if self.is_infinity() {
+            return *q;
+        }
+        if q.is_infinity() {
+            return *self;
+        }
+
+        let z1z1 = self.z.square();
+        let z2z2 = q.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = q.x * z1z1;
+        let s1 = self.y * q.z * z2z2;
+        let s2 = q.y * self.z * z1z1;
+
+        self.finish_add(u1, u2, s1, s2, self.z * q.z)
+    }
+
+    /// addition with an affine point (z = 1), which saves a few multiplications
+    pub fn add_affine(&self, q: &AffinePoint<C>) -> Self {
+        if q.is_infinity() {
+            return *self;
+        }
+        if self.is_infinity() {
+            return Self::from_affine(q);
+        }
+
+        let z1z1 = self.z.square();
+        let u2 = q.x * z1z1;
+        let s2 = q.y * self.z * z1z1;
+
+        self.finish_add(self.x, u2, self.y, s2, self.z)
+    }
+
+    /// shared tail of the addition formulas, `z` is the product of both z coordinates
+    fn finish_add(
+        &self,
+        u1: CurveField<C>,
+        u2: CurveField<C>,
+        s1: CurveField<C>,
+        s2: CurveField<C>,
+        z: CurveField<C>,
+    ) -> Self {
+        let h = u2 - u1;
+        let r = s2 - s1;
+
+        if h.is_zero() {
+            // the points have the same x coordinate, so they are either equal or inverses
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::infinity()
+            };
+        }
+
+        let hh = h.square();
+        let hhh = h * hh;
+        let v = u1 * hh;
+
+        let x = r.square() - hhh - v.double();
+        let y = r * (v - x) - s1 * hhh;
+        let z = z * h;
+
+        Self { x, y, z }
+    }
+}
+
+impl<C: Curve> PartialEq for JacobianPoint<C> {
     fn eq(&self, other: &Self) -> bool {
-        return self.x.eq(&other.x) && self.y.eq(&other.y);
+        // compare x1 * z2^2 == x2 * z1^2 and y1 * z2^3 == y2 * z1^3
+        if self.is_infinity() || other.is_infinity() {
+            return self.is_infinity() && other.is_infinity();
+        }
+
+        let z1z1 = self.z.square();
+        let z2z2 = other.z.square();
+
+        self.x * z2z2 == other.x * z1z1 && self.y * z2z2 * other.z == other.y * z1z1 * self.z
     }
 }
 
-impl Eq for Point {}
+impl<C: Curve> Eq for JacobianPoint<C> {}
 
 #[cfg(test)]
 mod test {
@@ -146,6 +872,13 @@ mod test {
 
     use super::*;
 
+    fn scalar(n: u64) -> Scalar {
+        Scalar::from_u64(n)
+    }
+
+    /// floor(O / 2), the boundary between "positive" and "negative" scalars
+    static HALF: Lazy<Scalar> = Lazy::new(|| Scalar::from_bigint(&(O.clone() / 2)));
+
     #[test]
     fn mod_inverse_base() {
         assert_eq!(
@@ -154,6 +887,14 @@ mod test {
         )
     }
 
+    #[test]
+    fn on_curve() {
+        assert!(G.is_on_curve());
+        assert!(G.mul(&scalar(1337)).is_on_curve());
+        assert!(!Point::infinity().is_on_curve());
+        assert!(!Point::new(G.x, G.y + Fp::ONE).is_on_curve());
+    }
+
     #[test]
     fn add_infs() {
         assert_eq!(Point::infinity(), Point::infinity().add(&Point::infinity()))
@@ -161,9 +902,9 @@ mod test {
 
     #[test]
     fn add_symmetry() {
-        assert_eq!(G.clone(), G.clone().add(&Point::infinity()));
+        assert_eq!(*G, G.add(&Point::infinity()));
 
-        assert_eq!(G.clone(), Point::infinity().add(&G.clone()))
+        assert_eq!(*G, Point::infinity().add(&G))
     }
 
     #[test]
@@ -173,8 +914,316 @@ mod test {
             "1AE168FEA63DC339A3C58419466CEAEEF7F632653266D0E1236431A950CFE52A",
         );
 
-        assert_eq!(double_g, G.add(&G.clone()));
-        assert_eq!(double_g, G.mul(&BigInt::from(2)))
+        assert_eq!(double_g, G.add(&G));
+        assert_eq!(double_g, G.mul(&scalar(2)))
+    }
+
+    #[test]
+    fn add_inverse() {
+        assert_eq!(Point::infinity(), G.add(&G.inverse()));
+    }
+
+    #[test]
+    fn jacobian_add_matches_mixed_add() {
+        let p = G.mul_jacobian(&scalar(5));
+        let q = G.mul(&scalar(7));
+
+        assert_eq!(p.add(&q.to_jacobian()), p.add_affine(&q));
+        assert_eq!(p.add_affine(&q).to_affine(), G.mul(&scalar(12)));
+    }
+
+    #[test]
+    fn jacobian_double_matches_add() {
+        let p = G.mul_jacobian(&scalar(3));
+
+        assert_eq!(p.double(), p.add(&p));
+        assert_eq!(p.double().to_affine(), G.mul(&scalar(6)));
+    }
+
+    #[test]
+    fn mul_small_scalars() {
+        let mut expected = Point::infinity();
+
+        for i in 0..40 {
+            assert_eq!(expected, G.mul(&scalar(i)));
+            expected = expected.add(&G);
+        }
+    }
+
+    #[test]
+    fn mul_negative() {
+        assert_eq!(G.mul(&-scalar(13)), G.mul(&scalar(13)).inverse());
+    }
+
+    #[test]
+    fn mul_order() {
+        assert_eq!(Point::infinity(), G.mul(&Scalar::from_bigint(&O)));
+        assert_eq!(*G, G.mul(&Scalar::from_bigint(&(O.clone() + 1))));
+        assert_eq!(Point::infinity(), G.mul(&-Scalar::ONE).add(&G));
+    }
+
+    #[test]
+    fn batch_to_affine() {
+        let points = vec![
+            G.mul_jacobian(&scalar(3)),
+            JacobianPoint::infinity(),
+            G.mul_jacobian(&scalar(5)),
+            G.to_jacobian().double(),
+        ];
+
+        let expected: Vec<Point> = points.iter().map(|p| p.to_affine()).collect();
+        assert_eq!(expected, JacobianPoint::batch_to_affine(&points));
+    }
+
+    #[test]
+    fn g_table_matches_mul() {
+        let scalars = [
+            scalar(0),
+            scalar(1),
+            scalar(255),
+            scalar(256),
+            -scalar(7),
+            Scalar::from_bigint(
+                &BigInt::from_str("1234567890123456789012345678901234567890").unwrap(),
+            ),
+            -Scalar::ONE,
+        ];
+
+        for scalar in scalars.iter() {
+            assert_eq!(G.mul(scalar), G_TABLE.mul(scalar));
+        }
+    }
+
+    #[test]
+    fn mul_add_matches_separate_muls() {
+        use num_bigint::RandBigInt;
+
+        let mut rand = rand::thread_rng();
+        let mut random_scalar =
+            || Scalar::from_bigint(&rand.gen_bigint_range(&BigInt::from(0), &O));
+
+        let q = G.mul(&random_scalar());
+        let mut pairs = vec![
+            (scalar(0), scalar(0)),
+            (scalar(1), scalar(0)),
+            (scalar(0), scalar(1)),
+            (scalar(7), -scalar(7)),
+            (-Scalar::ONE, -Scalar::ONE),
+        ];
+        for _ in 0..8 {
+            pairs.push((random_scalar(), random_scalar()));
+        }
+
+        for (u1, u2) in pairs {
+            let expected = G.mul(&u1).add(&q.mul(&u2));
+
+            assert_eq!(G_TABLE.mul_add(&u1, &q, &u2).to_affine(), expected);
+            assert_eq!(
+                G_TABLE.mul_add(&u1, &Point::infinity(), &u2).to_affine(),
+                G.mul(&u1)
+            );
+        }
+
+        // Q = G, so the sum cancels out
+        assert!(G_TABLE.mul_add(&scalar(5), &G, &-scalar(5)).is_infinity());
+    }
+
+    /// checks that the constant time and variable time paths agree
+    fn assert_mul_paths_agree(point: &Point, scalar: &Scalar) {
+        assert_eq!(
+            point.mul(scalar),
+            point.mul_secret(scalar),
+            "scalar {}",
+            scalar
+        );
+        assert_eq!(
+            G_TABLE.mul(scalar),
+            G.mul_secret(scalar),
+            "scalar {}",
+            scalar
+        );
+        assert_eq!(
+            G_TABLE.mul(scalar),
+            G_TABLE.mul_secret(scalar),
+            "scalar {}",
+            scalar
+        );
+    }
+
+    #[test]
+    fn mul_secret_edge_scalars() {
+        let scalars = [
+            scalar(0),
+            scalar(1),
+            scalar(2),
+            -scalar(1),
+            -scalar(2),
+            // the largest scalar that needs 2 * O added to it in the ladder, and the next one
+            Scalar::from_bigint(&((BigInt::from(1) << 256u32) - &*O - 1)),
+            Scalar::from_bigint(&((BigInt::from(1) << 256u32) - &*O)),
+            // zero bytes at the bottom, in the middle and at the top of the scalar
+            scalar(0x100),
+            scalar(0xff00_0000_00ff),
+            Scalar::from_bigint(&(BigInt::from(1) << 255u32)),
+        ];
+
+        let point = G.mul(&scalar(1337));
+
+        for scalar in scalars.iter() {
+            assert_mul_paths_agree(&G, scalar);
+            assert_mul_paths_agree(&point, scalar);
+        }
+    }
+
+    #[test]
+    fn mul_secret_random_scalars() {
+        use num_bigint::RandBigInt;
+
+        let mut rand = rand::thread_rng();
+        let point = G.mul(&Scalar::from_bigint(
+            &rand.gen_bigint_range(&BigInt::from(1), &O),
+        ));
+
+        for _ in 0..16 {
+            let scalar = Scalar::from_bigint(&rand.gen_bigint_range(&BigInt::from(0), &O));
+            assert_mul_paths_agree(&G, &scalar);
+            assert_mul_paths_agree(&point, &scalar);
+        }
+    }
+
+    #[test]
+    fn mul_secret_infinity() {
+        assert_eq!(Point::infinity(), Point::infinity().mul_secret(&scalar(5)));
+    }
+
+    #[test]
+    fn conditional_swap() {
+        let mut a = G.to_jacobian();
+        let mut b = G.to_jacobian().double();
+
+        JacobianPoint::conditional_swap(&mut a, &mut b, false);
+        assert_eq!(a.to_affine(), *G);
+
+        JacobianPoint::conditional_swap(&mut a, &mut b, true);
+        assert_eq!(b.to_affine(), *G);
+        assert_eq!(a.to_affine(), G.mul(&scalar(2)));
+    }
+
+    #[test]
+    fn wnaf_digits() {
+        let scalar = BigInt::from_str("1234567890123456789012345678901234567890").unwrap();
+        let digits = wnaf(&Scalar::from_bigint(&scalar), WNAF_WINDOW);
+
+        let mut reconstructed = BigInt::default();
+        for digit in digits.iter().rev() {
+            reconstructed = reconstructed * 2 + digit;
+        }
+
+        assert_eq!(scalar, reconstructed);
+        assert!(digits
+            .iter()
+            .all(|d| *d == 0 || (d % 2 != 0 && d.abs() < 16)));
+
+        // the largest scalar has a digit past bit 255
+        let digits = wnaf(&-Scalar::ONE, WNAF_WINDOW);
+        let mut reconstructed = BigInt::default();
+        for digit in digits.iter().rev() {
+            reconstructed = reconstructed * 2 + digit;
+        }
+        assert_eq!(O.clone() - 1, reconstructed);
+    }
+
+    /// plain double-and-add, the reference for the GLV multiplication
+    fn mul_double_and_add(point: &Point, scalar: &Scalar) -> Point {
+        let mut result = JacobianPoint::infinity();
+        for byte in scalar.to_bytes_be() {
+            for i in (0..8).rev() {
+                result = result.double();
+                if (byte >> i) & 1 == 1 {
+                    result = result.add_affine(point);
+                }
+            }
+        }
+        result.to_affine()
+    }
+
+    #[test]
+    fn endomorphism_is_lambda_mul() {
+        let point = G.mul(&scalar(1337));
+
+        assert_eq!(G.endomorphism(), G.mul_secret(&LAMBDA));
+        assert_eq!(point.endomorphism(), point.mul_secret(&LAMBDA));
+        assert_eq!(
+            G.mul_jacobian(&scalar(3)).endomorphism().to_affine(),
+            G.mul(&scalar(3)).endomorphism()
+        );
+        assert_eq!(*BETA * *BETA * *BETA, Fp::ONE);
+        assert_eq!(*LAMBDA * *LAMBDA * *LAMBDA, Scalar::ONE);
+    }
+
+    #[test]
+    fn glv_decompose_recombines() {
+        use num_bigint::RandBigInt;
+
+        let mut rand = rand::thread_rng();
+        let mut scalars = vec![
+            scalar(0),
+            scalar(1),
+            -Scalar::ONE,
+            *LAMBDA,
+            -*LAMBDA,
+            *HALF,
+            *HALF + Scalar::ONE,
+            Scalar::from_bigint(&(BigInt::from(1) << 128u32)),
+            Scalar::from_bigint(&(BigInt::from(1) << 255u32)),
+        ];
+        for _ in 0..64 {
+            scalars.push(Scalar::from_bigint(
+                &rand.gen_bigint_range(&BigInt::from(0), &O),
+            ));
+        }
+
+        let limit = Scalar::from_bigint(&(BigInt::from(1) << 128u32));
+
+        for k in scalars {
+            let (k1, k2) = glv_decompose(&k);
+
+            assert_eq!(k1 + k2 * *LAMBDA, k, "scalar {}", k);
+            for half in [k1, k2] {
+                assert!(half < limit || -half < limit, "scalar {}", k);
+            }
+        }
+    }
+
+    #[test]
+    fn glv_mul_matches_double_and_add() {
+        use num_bigint::RandBigInt;
+
+        let mut rand = rand::thread_rng();
+        let points = [*G, G.mul(&scalar(1337)), G.mul(&-scalar(99))];
+
+        let mut scalars = vec![
+            scalar(0),
+            scalar(1),
+            scalar(2),
+            -Scalar::ONE,
+            -scalar(2),
+            *LAMBDA,
+            *HALF,
+            Scalar::from_hex("ffffffffffffffffffffffffffffffff").unwrap(),
+            Scalar::from_hex("100000000000000000000000000000000").unwrap(),
+        ];
+        for _ in 0..8 {
+            scalars.push(Scalar::from_bigint(
+                &rand.gen_bigint_range(&BigInt::from(0), &O),
+            ));
+        }
+
+        for point in points.iter() {
+            for k in scalars.iter() {
+                assert_eq!(point.mul(k), mul_double_and_add(point, k), "scalar {}", k);
+            }
+        }
     }
 
     #[test]
@@ -184,7 +1233,7 @@ mod test {
             "0AB0902E8D880A89758212EB65CDAF473A1A06DA521FA91F29B5CB52DB03ED81",
         );
 
-        assert_eq!(expected, G.mul(&BigInt::from(13)))
+        assert_eq!(expected, G.mul(&scalar(13)))
     }
 
     #[test]
@@ -194,7 +1243,7 @@ mod test {
             "F7E3507399E595929DB99F34F57937101296891E44D23F0BE1F32CCE69616821",
         );
 
-        assert_eq!(expected, G.mul(&BigInt::from(16)))
+        assert_eq!(expected, G.mul(&scalar(16)))
     }
 
     #[test]
@@ -204,7 +1253,7 @@ mod test {
             "12BA26DCB10EC1625DA61FA10A844C676162948271D96967450288EE9233DC3A",
         );
 
-        assert_eq!(expected, G.mul(&BigInt::from(20)))
+        assert_eq!(expected, G.mul(&scalar(20)))
     }
 
     #[test]
@@ -214,7 +1263,7 @@ mod test {
             "2753DDD9C91A1C292B24562259363BD90877D8E454F297BF235782C459539959",
         );
 
-        assert_eq!(expected, G.mul(&BigInt::from_str("115792089237316195423570985008687907852837564279074904382605163141518161494332").unwrap()))
+        assert_eq!(expected, G.mul(&Scalar::from_bigint(&BigInt::from_str("115792089237316195423570985008687907852837564279074904382605163141518161494332").unwrap())))
     }
 
     #[test]
@@ -224,6 +1273,6 @@ mod test {
             "B7C52588D95C3B9AA25B0403F1EEF75702E84BB7597AABE663B82F6F04EF2777",
         );
 
-        assert_eq!(expected, G.mul(&BigInt::from_str("115792089237316195423570985008687907852837564279074904382605163141518161494336").unwrap()))
+        assert_eq!(expected, G.mul(&Scalar::from_bigint(&BigInt::from_str("115792089237316195423570985008687907852837564279074904382605163141518161494336").unwrap())))
     }
 }
@@ -0,0 +1,160 @@
+//! Elliptic curve Diffie-Hellman on secp256k1.
+//!
+//! Both sides multiply their private key with the other side's public key and end up with
+//! the same point. Its x coordinate is the shared secret used by RLPx and ECIES, libraries
+//! built on libsecp256k1 hash it together with the parity of y instead.
+
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use super::{PrivateKey, PublicKey, PublicKeyError};
+
+/// the result of a Diffie-Hellman exchange, wiped from memory when dropped
+pub struct SharedSecret {
+    x: [u8; 32],
+    y_is_odd: bool,
+}
+
+impl SharedSecret {
+    /// multiplies the peer's public key with our private key, in constant time
+    ///
+    /// a `PublicKey` is always a valid point, and since the curve has a cofactor of 1
+    /// there are no small subgroups to worry about
+    pub fn new(private_key: &PrivateKey, peer: &PublicKey) -> Self {
+        let point = peer.point().mul_secret(&private_key.scalar());
+
+        // the private key is in [1, O) and the peer point has order O
+        debug_assert!(!point.is_infinity());
+
+        Self {
+            x: point.x.to_bytes_be(),
+            y_is_odd: point.y.is_odd(),
+        }
+    }
+
+    /// like `new`, but decodes and validates a SEC1 encoded peer key first
+    pub fn from_peer_bytes(private_key: &PrivateKey, peer: &[u8]) -> Result<Self, PublicKeyError> {
+        Ok(Self::new(private_key, &PublicKey::from_bytes(peer)?))
+    }
+
+    /// the raw x coordinate of the shared point, as used by RLPx and ECIES
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.x
+    }
+
+    /// sha256 of the compressed shared point, the default of libsecp256k1's `secp256k1_ecdh`
+    pub fn hashed(&self) -> [u8; 32] {
+        let prefix = if self.y_is_odd { 3u8 } else { 2u8 };
+
+        Sha256::new()
+            .chain_update([prefix])
+            .chain_update(self.x)
+            .finalize()
+            .into()
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.x.zeroize();
+        self.y_is_odd.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys() -> (PrivateKey, PrivateKey) {
+        (
+            PrivateKey::from_hex(
+                "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93",
+            )
+            .unwrap(),
+            PrivateKey::from_hex(
+                "7b2f17cf50ef33bcb8b404d718b2e1fde3f2d025fe34f8d3f4c6e526e447ef13",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn shared_secret_vector() {
+        let (alice, bob) = keys();
+        let secret = SharedSecret::new(&alice, &bob.public_key());
+
+        assert_eq!(
+            hex::encode(secret.to_bytes()),
+            "1c93ed386994e42357ff04000beed2137f0d1591ce29e00310062222d91bc9ab"
+        );
+        assert_eq!(
+            hex::encode(secret.hashed()),
+            "6b3b7100679369217494a2ff04c827c32096ab4996d1f93a2819e61048d7e2e3"
+        );
+    }
+
+    #[test]
+    fn geth_shared_key_vector() {
+        // TestSharedKeyStatic in go-ethereum's crypto/ecies, the hashed value was computed
+        // with OpenSSL's secp256k1 as sha256 of the compressed shared point
+        let first = PrivateKey::from_hex(
+            "7ebbc6a8358bc76dd73ebc557056702c8cfc34e5cfcd90eb83af0347575fd2ad",
+        )
+        .unwrap();
+        let second = PrivateKey::from_hex(
+            "6a3d6396903245bba5837752b9e0348874e72db0c4e11e9c485a81b4ea4353b9",
+        )
+        .unwrap();
+        let secret = SharedSecret::new(&first, &second.public_key());
+
+        assert_eq!(
+            hex::encode(secret.to_bytes()),
+            "167ccc13ac5e8a26b131c3446030c60fbfac6aa8e31149d0869f93626a4cdf62"
+        );
+        assert_eq!(
+            hex::encode(secret.hashed()),
+            "fe9d6604d5ebc50d933f3463e166ca7e8410f2a23a0290a7940a7f88bab2cc5b"
+        );
+    }
+
+    #[test]
+    fn both_sides_agree() {
+        let alice = PrivateKey::random();
+        let bob = PrivateKey::random();
+
+        let alice_secret = SharedSecret::new(&alice, &bob.public_key());
+        let bob_secret = SharedSecret::new(&bob, &alice.public_key());
+
+        assert_eq!(alice_secret.to_bytes(), bob_secret.to_bytes());
+        assert_eq!(alice_secret.hashed(), bob_secret.hashed());
+        assert_eq!(
+            alice.diffie_hellman(&bob.public_key()).to_bytes(),
+            alice_secret.to_bytes()
+        );
+    }
+
+    #[test]
+    fn peer_bytes_are_validated() {
+        let (alice, bob) = keys();
+        let bob_public = bob.public_key();
+
+        let compressed = SharedSecret::from_peer_bytes(&alice, &bob_public.to_bytes_compressed());
+        let uncompressed =
+            SharedSecret::from_peer_bytes(&alice, &bob_public.to_bytes_uncompressed());
+        assert_eq!(
+            compressed.unwrap().to_bytes(),
+            uncompressed.unwrap().to_bytes()
+        );
+
+        let mut invalid = bob_public.to_bytes_uncompressed();
+        invalid[64] ^= 1;
+        assert_eq!(
+            SharedSecret::from_peer_bytes(&alice, &invalid).err(),
+            Some(PublicKeyError::NotOnCurve)
+        );
+        assert_eq!(
+            SharedSecret::from_peer_bytes(&alice, &[0]).err(),
+            Some(PublicKeyError::Infinity)
+        );
+    }
+}
@@ -0,0 +1,846 @@
+//! Fixed width arithmetic modulo 256 bit primes.
+//!
+//! Elements are stored on the stack as four 64 bit limbs (least significant first)
+//! in Montgomery form, i.e. `a` is stored as `a * 2^256 mod m`. Multiplication then
+//! only needs word sized multiplications and no divisions, and none of the operations
+//! allocate. Addition, subtraction and multiplication don't branch on their inputs.
+//!
+//! A field only has to implement `PrimeField` with its Montgomery constants, everything
+//! else (exponentiation, inversion, square roots, the Legendre symbol) is shared and
+//! derived from the modulus at compile time.
+
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use num_bigint::{BigInt, Sign};
+
+use super::number_theory::modulo;
+
+/// the integers modulo a 256 bit prime `m`, whose elements are `FieldElement<Self>`
+///
+/// implementations only provide the constants needed for Montgomery arithmetic,
+/// the exponents below have defaults computed from the modulus
+pub trait PrimeField: Debug + Clone + Copy + PartialEq + Eq + 'static {
+    const MODULUS: [u64; 4];
+    /// 2^256 mod m, which is 1 in Montgomery form
+    const R: [u64; 4];
+    /// 2^512 mod m, used to convert numbers into Montgomery form
+    const R2: [u64; 4];
+    /// -m^-1 mod 2^64
+    const INV: u64;
+
+    /// m - 2, x^(m - 2) is the inverse of x by Fermat's little theorem
+    const INVERSE_EXPONENT: [u64; 4] = sub_small(Self::MODULUS, 2);
+    /// (m - 1) / 2, x^((m - 1) / 2) is the Legendre symbol of x by Euler's criterion
+    const LEGENDRE_EXPONENT: [u64; 4] = shr(Self::MODULUS, 1);
+    /// the largest s such that 2^s divides m - 1, it's 1 for all m = 3 (mod 4)
+    const TWO_ADICITY: u32 = trailing_zeros(sub_small(Self::MODULUS, 1));
+    /// (t - 1) / 2, where m - 1 = 2^s * t with t odd
+    /// for m = 3 (mod 4) multiplying x by x^((t - 1) / 2) gives x^((m + 1) / 4)
+    const SQRT_EXPONENT: [u64; 4] = shr(Self::MODULUS, Self::TWO_ADICITY + 1);
+}
+
+/// the secp256k1 base field, the coordinates of curve points are integers mod P
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Secp256k1Field;
+
+impl PrimeField for Secp256k1Field {
+    const MODULUS: [u64; 4] = [
+        0xfffffffefffffc2f,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ];
+    const R: [u64; 4] = [0x00000001000003d1, 0, 0, 0];
+    const R2: [u64; 4] = [0x000007a2000e90a1, 0x0000000000000001, 0, 0];
+    const INV: u64 = 0xd838091dd2253531;
+}
+
+/// the secp256k1 scalar field, private keys, nonces and signatures are integers mod O
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Secp256k1Scalar;
+
+impl PrimeField for Secp256k1Scalar {
+    const MODULUS: [u64; 4] = [
+        0xbfd25e8cd0364141,
+        0xbaaedce6af48a03b,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+    ];
+    const R: [u64; 4] = [
+        0x402da1732fc9bebf,
+        0x4551231950b75fc4,
+        0x0000000000000001,
+        0x0000000000000000,
+    ];
+    const R2: [u64; 4] = [
+        0x896cf21467d7d140,
+        0x741496c20e7cf878,
+        0xe697f5e45bcd07c6,
+        0x9d671cd581c69bc5,
+    ];
+    const INV: u64 = 0x4b0dff665588b13f;
+}
+
+/// an element of the secp256k1 base field
+pub type Fp = FieldElement<Secp256k1Field>;
+
+/// an element of the secp256k1 scalar field
+pub type Scalar = FieldElement<Secp256k1Scalar>;
+
+/// an integer mod `M::MODULUS`, always fully reduced so equality is just limb equality
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FieldElement<M: PrimeField> {
+    limbs: [u64; 4],
+    modulus: PhantomData<M>,
+}
+
+impl<M: PrimeField> FieldElement<M> {
+    pub const ZERO: Self = Self::from_montgomery([0; 4]);
+    pub const ONE: Self = Self::from_montgomery(M::R);
+
+    const fn from_montgomery(limbs: [u64; 4]) -> Self {
+        Self {
+            limbs,
+            modulus: PhantomData,
+        }
+    }
+
+    /// converts a 256 bit number (least significant limb first) into a field element,
+    /// reducing it if it's not smaller than the modulus
+    pub fn from_raw(limbs: [u64; 4]) -> Self {
+        // montgomery_mul(x, R^2) = x * R mod m, this is correct for any x < 2^256
+        Self::from_montgomery(montgomery_mul::<M>(&limbs, &M::R2))
+    }
+
+    /// the canonical value of the element, least significant limb first
+    pub fn to_raw(self) -> [u64; 4] {
+        montgomery_mul::<M>(&self.limbs, &[1, 0, 0, 0])
+    }
+
+    pub fn from_u64(n: u64) -> Self {
+        Self::from_raw([n, 0, 0, 0])
+    }
+
+    /// parses a big endian number, returns `None` if it's not smaller than the modulus
+    pub fn from_bytes_be(bytes: &[u8; 32]) -> Option<Self> {
+        let raw = raw_from_bytes_be(bytes);
+
+        if lt_raw(&raw, &M::MODULUS) {
+            Some(Self::from_raw(raw))
+        } else {
+            None
+        }
+    }
+
+    /// parses a big endian number and reduces it mod m
+    pub fn from_bytes_be_reduced(bytes: &[u8; 32]) -> Self {
+        Self::from_raw(raw_from_bytes_be(bytes))
+    }
+
+    pub fn to_bytes_be(self) -> [u8; 32] {
+        let raw = self.to_raw();
+        let mut bytes = [0u8; 32];
+
+        for (i, limb) in raw.iter().enumerate() {
+            bytes[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    /// parses a big endian hex number of at most 64 digits,
+    /// returns `None` if it's invalid or not smaller than the modulus
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() > 64 {
+            return None;
+        }
+
+        let padded = format!("{:0>64}", hex);
+        let bytes: [u8; 32] = hex::decode(padded).ok()?.try_into().ok()?;
+        Self::from_bytes_be(&bytes)
+    }
+
+    /// converts any integer, including negative ones, reducing it mod m
+    pub fn from_bigint(n: &BigInt) -> Self {
+        let reduced = modulo(n, &Self::modulus_bigint());
+        let mut bytes = [0u8; 32];
+        let reduced = reduced.to_bytes_be().1;
+        bytes[32 - reduced.len()..].copy_from_slice(&reduced);
+        Self::from_bytes_be_reduced(&bytes)
+    }
+
+    pub fn to_bigint(self) -> BigInt {
+        BigInt::from_bytes_be(Sign::Plus, &self.to_bytes_be())
+    }
+
+    pub fn modulus_bigint() -> BigInt {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in M::MODULUS.iter().enumerate() {
+            bytes[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+        }
+        BigInt::from_bytes_be(Sign::Plus, &bytes)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs == [0; 4]
+    }
+
+    /// whether the canonical value is odd, e.g. the "sign" of a y coordinate
+    pub fn is_odd(&self) -> bool {
+        self.to_raw()[0] & 1 == 1
+    }
+
+    pub fn square(&self) -> Self {
+        *self * *self
+    }
+
+    pub fn double(&self) -> Self {
+        *self + *self
+    }
+
+    /// self^exp with a fixed window of 4 bits, the sequence of operations depends only
+    /// on the exponent, so this runs in constant time for secret bases and public exponents
+    pub fn pow(&self, exp: &[u64; 4]) -> Self {
+        let mut table = [Self::ONE; 16];
+        for i in 1..16 {
+            table[i] = table[i - 1] * *self;
+        }
+
+        let mut result = Self::ONE;
+        for limb in exp.iter().rev() {
+            for shift in (0..64).step_by(4).rev() {
+                result = result.square().square().square().square();
+                result = result * table[(limb >> shift) as usize & 0xf];
+            }
+        }
+
+        result
+    }
+
+    /// the multiplicative inverse by Fermat's little theorem, x^(m - 2)
+    /// zero is mapped to zero, and the running time doesn't depend on the element
+    pub fn invert(&self) -> Self {
+        self.pow(&M::INVERSE_EXPONENT)
+    }
+
+    /// the Legendre symbol by Euler's criterion: 1 for non-zero squares, -1 for non-squares
+    /// and 0 for zero, in constant time
+    pub fn legendre(&self) -> i8 {
+        let symbol = self.pow(&M::LEGENDRE_EXPONENT);
+
+        if symbol == Self::ONE {
+            1
+        } else if symbol.is_zero() {
+            0
+        } else {
+            -1
+        }
+    }
+
+    /// the Jacobi symbol with the binary algorithm, which is the same as the Legendre symbol
+    /// since the modulus is prime, but much faster, it's variable time though
+    pub fn jacobi(&self) -> i8 {
+        let mut a = self.to_raw();
+        let mut n = M::MODULUS;
+        let mut symbol = 1;
+
+        while a != [0; 4] {
+            while a[0] & 1 == 0 {
+                a = shr(a, 1);
+                // (2 / n) is -1 for n = 3 or 5 (mod 8)
+                if matches!(n[0] & 7, 3 | 5) {
+                    symbol = -symbol;
+                }
+            }
+
+            // quadratic reciprocity, (a / n) = -(n / a) if both are 3 (mod 4)
+            if lt_raw(&a, &n) {
+                std::mem::swap(&mut a, &mut n);
+                if a[0] & 3 == 3 && n[0] & 3 == 3 {
+                    symbol = -symbol;
+                }
+            }
+
+            a = sub_raw(&a, &n).0;
+        }
+
+        if n == [1, 0, 0, 0] {
+            symbol
+        } else {
+            0
+        }
+    }
+
+    /// a square root, if one exists (which one of the two roots is unspecified)
+    ///
+    /// for m = 3 (mod 4) this is a single exponentiation, x^((m + 1) / 4), other moduli
+    /// (like the secp256k1 and P-256 scalar fields) fall back to Tonelli-Shanks
+    pub fn sqrt(&self) -> Option<Self> {
+        let w = self.pow(&M::SQRT_EXPONENT);
+        // x^((t + 1) / 2)
+        let mut root = *self * w;
+
+        if M::TWO_ADICITY == 1 {
+            return if root.square() == *self {
+                Some(root)
+            } else {
+                None
+            };
+        }
+
+        if self.is_zero() {
+            return Some(Self::ZERO);
+        }
+
+        // Tonelli-Shanks, the invariant is root^2 = x * b where the order of b divides 2^(v - 1)
+        // https://eprint.iacr.org/2012/685.pdf (algorithm 5)
+        let mut b = root * w;
+        let mut c = Self::non_residue().pow(&M::SQRT_EXPONENT);
+        // g^t = (g^((t - 1) / 2))^2 * g
+        c = c.square() * Self::non_residue();
+        let mut v = M::TWO_ADICITY;
+
+        while b != Self::ONE {
+            // the smallest k with b^(2^k) = 1
+            let mut k = 0;
+            let mut b2k = b;
+            while b2k != Self::ONE {
+                b2k = b2k.square();
+                k += 1;
+
+                // b doesn't have order 2^(v - 1), so x is not a square
+                if k == v {
+                    return None;
+                }
+            }
+
+            let mut w = c;
+            for _ in 0..v - k - 1 {
+                w = w.square();
+            }
+
+            root = root * w;
+            c = w.square();
+            b = b * c;
+            v = k;
+        }
+
+        Some(root)
+    }
+
+    /// the smallest quadratic non-residue, only used by Tonelli-Shanks
+    fn non_residue() -> Self {
+        (2..)
+            .map(Self::from_u64)
+            .find(|n| n.jacobi() == -1)
+            .expect("every odd prime field has non-residues")
+    }
+
+    /// inverts every element in place with a single inversion, using Montgomery's trick
+    /// zeros have no inverse and are left as they are
+    pub fn batch_invert(elements: &mut [Self]) {
+        // prefix products of all the non-zero elements
+        let mut products = Vec::with_capacity(elements.len());
+        let mut acc = Self::ONE;
+        for element in elements.iter() {
+            products.push(acc);
+            if !element.is_zero() {
+                acc = acc * *element;
+            }
+        }
+
+        let mut acc_inv = acc.invert();
+
+        for (element, product) in elements.iter_mut().zip(products).rev() {
+            if element.is_zero() {
+                continue;
+            }
+
+            let inverse = acc_inv * product;
+            acc_inv = acc_inv * *element;
+            *element = inverse;
+        }
+    }
+
+    /// returns `b` if `choice` is set and `a` otherwise, without branching on `choice`
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        let mut limbs = [0u64; 4];
+
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = a.limbs[i] ^ (mask & (a.limbs[i] ^ b.limbs[i]));
+        }
+
+        Self::from_montgomery(limbs)
+    }
+
+    /// swaps `a` and `b` if `choice` is set, without branching on `choice`
+    pub fn conditional_swap(a: &mut Self, b: &mut Self, choice: bool) {
+        let mask = 0u64.wrapping_sub(choice as u64);
+
+        for i in 0..4 {
+            let t = mask & (a.limbs[i] ^ b.limbs[i]);
+            a.limbs[i] ^= t;
+            b.limbs[i] ^= t;
+        }
+    }
+}
+
+impl<M: PrimeField> Add for FieldElement<M> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (sum, carry) = add_raw(&self.limbs, &rhs.limbs);
+        Self::from_montgomery(reduce_once(sum, carry, &M::MODULUS))
+    }
+}
+
+impl<M: PrimeField> Sub for FieldElement<M> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_montgomery(sub_mod(&self.limbs, &rhs.limbs, &M::MODULUS))
+    }
+}
+
+impl<M: PrimeField> Mul for FieldElement<M> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::from_montgomery(montgomery_mul::<M>(&self.limbs, &rhs.limbs))
+    }
+}
+
+impl<M: PrimeField> Neg for FieldElement<M> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::ZERO - self
+    }
+}
+
+impl<M: PrimeField> PartialOrd for FieldElement<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M: PrimeField> Ord for FieldElement<M> {
+    /// compares the canonical values, not the Montgomery representations
+    fn cmp(&self, other: &Self) -> Ordering {
+        let a = self.to_raw();
+        let b = other.to_raw();
+        a.iter().rev().cmp(b.iter().rev())
+    }
+}
+
+impl<M: PrimeField> Debug for FieldElement<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{}", hex::encode(self.to_bytes_be()))
+    }
+}
+
+impl<M: PrimeField> Display for FieldElement<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{}", hex::encode(self.to_bytes_be()))
+    }
+}
+
+// helpers for 256 bit numbers stored as 4 limbs, least significant first
+
+/// a + b + carry, returns (result, carry)
+#[inline(always)]
+pub(crate) fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+/// a - b - borrow, returns (result, borrow)
+#[inline(always)]
+pub(crate) fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+/// a + b * c + carry, returns (result, carry)
+#[inline(always)]
+pub(crate) fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+pub(crate) fn add_raw(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut result = [0u64; 4];
+    let mut carry = 0;
+
+    for i in 0..4 {
+        (result[i], carry) = adc(a[i], b[i], carry);
+    }
+
+    (result, carry)
+}
+
+pub(crate) fn sub_raw(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut result = [0u64; 4];
+    let mut borrow = 0;
+
+    for i in 0..4 {
+        (result[i], borrow) = sbb(a[i], b[i], borrow);
+    }
+
+    (result, borrow)
+}
+
+pub(crate) fn lt_raw(a: &[u64; 4], b: &[u64; 4]) -> bool {
+    sub_raw(a, b).1 == 1
+}
+
+pub(crate) fn raw_from_bytes_be(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut raw = [0u64; 4];
+
+    for (i, limb) in raw.iter_mut().enumerate() {
+        *limb = u64::from_be_bytes(bytes[24 - 8 * i..32 - 8 * i].try_into().unwrap());
+    }
+
+    raw
+}
+
+/// a - b for a >= b, usable in constants
+const fn sub_small(a: [u64; 4], b: u64) -> [u64; 4] {
+    let mut result = a;
+    let mut borrow = b;
+    let mut i = 0;
+
+    while i < 4 {
+        let (diff, overflow) = result[i].overflowing_sub(borrow);
+        result[i] = diff;
+        borrow = overflow as u64;
+        i += 1;
+    }
+
+    result
+}
+
+/// a >> n for n < 256, usable in constants
+const fn shr(a: [u64; 4], n: u32) -> [u64; 4] {
+    let limbs = (n / 64) as usize;
+    let bits = n % 64;
+    let mut result = [0u64; 4];
+    let mut i = 0;
+
+    while i + limbs < 4 {
+        result[i] = a[i + limbs] >> bits;
+        if bits > 0 && i + limbs + 1 < 4 {
+            result[i] |= a[i + limbs + 1] << (64 - bits);
+        }
+        i += 1;
+    }
+
+    result
+}
+
+/// the number of trailing zero bits, usable in constants
+const fn trailing_zeros(a: [u64; 4]) -> u32 {
+    let mut i = 0;
+
+    while i < 4 {
+        if a[i] != 0 {
+            return i as u32 * 64 + a[i].trailing_zeros();
+        }
+        i += 1;
+    }
+
+    256
+}
+
+/// subtracts the modulus from a + carry * 2^256 if the result would still be positive,
+/// brings any number below 2 * m into the range [0, m)
+fn reduce_once(a: [u64; 4], carry: u64, modulus: &[u64; 4]) -> [u64; 4] {
+    let (diff, borrow) = sub_raw(&a, modulus);
+
+    // use the difference if there was a carry out of a or the subtraction didn't underflow
+    let use_diff = carry | (borrow ^ 1);
+    let mask = 0u64.wrapping_sub(use_diff);
+
+    let mut result = [0u64; 4];
+    for i in 0..4 {
+        result[i] = a[i] ^ (mask & (a[i] ^ diff[i]));
+    }
+
+    result
+}
+
+/// a - b mod m, for a and b already smaller than m
+fn sub_mod(a: &[u64; 4], b: &[u64; 4], modulus: &[u64; 4]) -> [u64; 4] {
+    let (diff, borrow) = sub_raw(a, b);
+
+    // add the modulus back if we wrapped around
+    let mask = 0u64.wrapping_sub(borrow);
+    let mut correction = *modulus;
+    for limb in correction.iter_mut() {
+        *limb &= mask;
+    }
+
+    add_raw(&diff, &correction).0
+}
+
+/// computes a * b * 2^-256 mod m with the CIOS method
+/// https://www.microsoft.com/en-us/research/wp-content/uploads/1996/01/j37acmon.pdf
+fn montgomery_mul<M: PrimeField>(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let m = &M::MODULUS;
+    let mut t = [0u64; 6];
+
+    for b_i in b.iter() {
+        let mut carry = 0;
+        for j in 0..4 {
+            (t[j], carry) = mac(t[j], a[j], *b_i, carry);
+        }
+        (t[4], t[5]) = adc(t[4], carry, 0);
+
+        // add a multiple of m that makes the lowest limb zero, then shift right by a limb
+        let k = t[0].wrapping_mul(M::INV);
+        let (_, mut carry) = mac(t[0], k, m[0], 0);
+        for j in 1..4 {
+            (t[j - 1], carry) = mac(t[j], k, m[j], carry);
+        }
+        let (limb, carry) = adc(t[4], carry, 0);
+        t[3] = limb;
+        t[4] = t[5] + carry;
+    }
+
+    reduce_once([t[0], t[1], t[2], t[3]], t[4], m)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::RandBigInt;
+
+    use crate::ecdsa::{
+        number_theory::{legendre_symbol, mod_inverse},
+        p256::{P256Field, P256Scalar},
+    };
+
+    use super::*;
+
+    fn check_constants<M: PrimeField>() {
+        let m = FieldElement::<M>::modulus_bigint();
+        let r = BigInt::from(1) << 256u32;
+
+        assert_eq!(FieldElement::<M>::ONE.to_bigint(), BigInt::from(1));
+        assert_eq!(
+            FieldElement::<M>::from_montgomery(M::R2).to_bigint(),
+            modulo(&r, &m)
+        );
+        assert_eq!(
+            modulo(&(&m * BigInt::from(M::INV) + 1), &BigInt::from(1u128 << 64)),
+            BigInt::default()
+        );
+
+        let exponent = |limbs: [u64; 4]| FieldElement::<M>::from_raw(limbs).to_bigint();
+        let t = (&m - 1) >> M::TWO_ADICITY;
+        assert_eq!(exponent(M::INVERSE_EXPONENT), &m - 2);
+        assert_eq!(exponent(M::LEGENDRE_EXPONENT), (&m - 1) / 2);
+        assert_eq!(exponent(M::SQRT_EXPONENT), (&t - 1) / 2);
+        assert_eq!(modulo(&t, &BigInt::from(2)), BigInt::from(1));
+    }
+
+    #[test]
+    fn constants() {
+        check_constants::<Secp256k1Field>();
+        check_constants::<Secp256k1Scalar>();
+        check_constants::<P256Field>();
+        check_constants::<P256Scalar>();
+    }
+
+    fn check_against_bigint<M: PrimeField>() {
+        let m = FieldElement::<M>::modulus_bigint();
+        let mut rand = rand::thread_rng();
+
+        let mut values = vec![BigInt::default(), BigInt::from(1), &m - 1, &m - 2];
+        for _ in 0..32 {
+            values.push(rand.gen_bigint_range(&BigInt::default(), &m));
+        }
+
+        for a in values.iter() {
+            for b in values.iter().take(8) {
+                let fa = FieldElement::<M>::from_bigint(a);
+                let fb = FieldElement::<M>::from_bigint(b);
+
+                assert_eq!((fa + fb).to_bigint(), modulo(&(a + b), &m));
+                assert_eq!((fa - fb).to_bigint(), modulo(&(a - b), &m));
+                assert_eq!((fa * fb).to_bigint(), modulo(&(a * b), &m));
+                assert_eq!((-fa).to_bigint(), modulo(&-a, &m));
+            }
+        }
+    }
+
+    #[test]
+    fn field_arithmetic() {
+        check_against_bigint::<Secp256k1Field>();
+    }
+
+    #[test]
+    fn scalar_arithmetic() {
+        check_against_bigint::<Secp256k1Scalar>();
+    }
+
+    #[test]
+    fn p256_arithmetic() {
+        check_against_bigint::<P256Field>();
+        check_against_bigint::<P256Scalar>();
+    }
+
+    /// compares inversion, square roots and the Legendre symbol with the BigInt versions
+    fn check_number_theory<M: PrimeField>() {
+        let m = FieldElement::<M>::modulus_bigint();
+        let mut rand = rand::thread_rng();
+
+        let mut values = vec![BigInt::from(1), BigInt::from(2), &m - 1];
+        for _ in 0..16 {
+            values.push(rand.gen_bigint_range(&BigInt::from(1), &m));
+        }
+
+        for a in values.iter() {
+            let element = FieldElement::<M>::from_bigint(a);
+            let symbol = legendre_symbol(a, &m);
+
+            assert_eq!(element.invert().to_bigint(), mod_inverse(a, &m));
+            assert_eq!(
+                element.legendre(),
+                if symbol == BigInt::from(1) { 1 } else { -1 }
+            );
+            assert_eq!(element.jacobi(), element.legendre());
+
+            match element.sqrt() {
+                Some(root) => assert_eq!(root.square(), element),
+                None => assert_eq!(element.legendre(), -1),
+            }
+
+            // every square has a root
+            assert!(element.square().sqrt().is_some());
+        }
+
+        let zero = FieldElement::<M>::ZERO;
+        assert_eq!(zero.invert(), zero);
+        assert_eq!(zero.sqrt(), Some(zero));
+        assert_eq!(zero.legendre(), 0);
+        assert_eq!(zero.jacobi(), 0);
+    }
+
+    #[test]
+    fn number_theory() {
+        // the base fields are 3 (mod 4), the scalar fields need Tonelli-Shanks
+        assert_eq!(Secp256k1Field::TWO_ADICITY, 1);
+        assert_eq!(P256Field::TWO_ADICITY, 1);
+        assert_eq!(Secp256k1Scalar::TWO_ADICITY, 6);
+        assert_eq!(P256Scalar::TWO_ADICITY, 4);
+
+        check_number_theory::<Secp256k1Field>();
+        check_number_theory::<Secp256k1Scalar>();
+        check_number_theory::<P256Field>();
+        check_number_theory::<P256Scalar>();
+    }
+
+    #[test]
+    fn pow() {
+        let a = Fp::from_u64(3);
+
+        assert_eq!(a.pow(&[0; 4]), Fp::ONE);
+        assert_eq!(a.pow(&[1, 0, 0, 0]), a);
+        assert_eq!(a.pow(&[5, 0, 0, 0]), Fp::from_u64(243));
+        assert_eq!(
+            a.pow(&[0, 1, 0, 0]).to_bigint(),
+            BigInt::from(3).modpow(&(BigInt::from(1) << 64u32), &Fp::modulus_bigint())
+        );
+        // Fermat's little theorem
+        assert_eq!(a.pow(&Secp256k1Field::MODULUS), a);
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let bytes = hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let element = Fp::from_bytes_be(&bytes).unwrap();
+
+        assert_eq!(element.to_bytes_be(), bytes);
+        assert_eq!(
+            Fp::from_hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+            Some(element)
+        );
+    }
+
+    #[test]
+    fn out_of_range_bytes() {
+        let modulus = Scalar::modulus_bigint().to_bytes_be().1.try_into().unwrap();
+
+        assert_eq!(Scalar::from_bytes_be(&modulus), None);
+        assert_eq!(Scalar::from_bytes_be_reduced(&modulus), Scalar::ZERO);
+        assert_eq!(
+            Scalar::from_bytes_be_reduced(&[0xff; 32]).to_bigint(),
+            modulo(
+                &((BigInt::from(1) << 256u32) - 1),
+                &Scalar::modulus_bigint()
+            )
+        );
+    }
+
+    #[test]
+    fn invert_and_sqrt() {
+        let a = Fp::from_u64(1337);
+
+        assert_eq!(a * a.invert(), Fp::ONE);
+        assert_eq!(Fp::ZERO.invert(), Fp::ZERO);
+
+        let root = a.square().sqrt().unwrap();
+        assert!(root == a || root == -a);
+
+        // 5^3 + 7 is not a square mod P
+        assert_eq!(Fp::from_u64(132).sqrt(), None);
+    }
+
+    #[test]
+    fn batch_invert() {
+        let mut elements = [
+            Scalar::from_u64(3),
+            Scalar::ZERO,
+            -Scalar::ONE,
+            Scalar::from_u64(1337),
+        ];
+        let expected: Vec<Scalar> = elements.iter().map(|e| e.invert()).collect();
+
+        Scalar::batch_invert(&mut elements);
+        assert_eq!(elements.to_vec(), expected);
+
+        Scalar::batch_invert(&mut []);
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Scalar::ONE < Scalar::from_u64(2));
+        assert!(Scalar::from_u64(2) < -Scalar::ONE);
+        assert!(Scalar::from_u64(1 << 40) > Scalar::from_u64(3));
+    }
+
+    #[test]
+    fn conditional_ops() {
+        let mut a = Fp::from_u64(1);
+        let mut b = Fp::from_u64(2);
+
+        assert_eq!(Fp::conditional_select(&a, &b, false), a);
+        assert_eq!(Fp::conditional_select(&a, &b, true), b);
+
+        Fp::conditional_swap(&mut a, &mut b, false);
+        assert_eq!(a, Fp::from_u64(1));
+
+        Fp::conditional_swap(&mut a, &mut b, true);
+        assert_eq!(a, Fp::from_u64(2));
+        assert_eq!(b, Fp::from_u64(1));
+    }
+}
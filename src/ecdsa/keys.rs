@@ -0,0 +1,477 @@
+//! Typed secp256k1 keys.
+//!
+//! `PrivateKey` can only hold a scalar in the range [1, O) and wipes its memory when
+//! dropped, `PublicKey` can only hold a point that's on the curve.
+
+use std::fmt::{Debug, Display};
+
+use rand::{rngs::OsRng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::address::Address;
+
+use super::{
+    curve::{Point, G_TABLE},
+    field::{Fp, Scalar},
+    SharedSecret,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivateKeyError {
+    /// private keys are exactly 32 bytes long
+    InvalidLength(usize),
+    InvalidHex,
+    /// the key is zero or not smaller than the curve order
+    OutOfRange,
+}
+
+impl Display for PrivateKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrivateKeyError::InvalidLength(len) => write!(f, "invalid private key length {}", len),
+            PrivateKeyError::InvalidHex => write!(f, "private key is not valid hex"),
+            PrivateKeyError::OutOfRange => write!(f, "private key is not in the range [1, O)"),
+        }
+    }
+}
+
+impl std::error::Error for PrivateKeyError {}
+
+/// a secp256k1 private key, stored as 32 big endian bytes
+///
+/// the bytes are zeroed when the key is dropped, note that the arithmetic on the key
+/// still goes through temporary `Scalar` copies which are not wiped
+pub struct PrivateKey([u8; 32]);
+
+impl PrivateKey {
+    /// generates a new key from the operating system's CSPRNG
+    pub fn random() -> Self {
+        let mut bytes = [0u8; 32];
+
+        // rejection sampling, the chance of needing a second try is about 2^-128
+        loop {
+            OsRng.fill_bytes(&mut bytes);
+
+            if let Ok(key) = Self::from_bytes(&bytes) {
+                bytes.zeroize();
+                return key;
+            }
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PrivateKeyError> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| PrivateKeyError::InvalidLength(bytes.len()))?;
+
+        match Scalar::from_bytes_be(&bytes) {
+            Some(scalar) if !scalar.is_zero() => {}
+            _ => return Err(PrivateKeyError::OutOfRange),
+        }
+
+        Ok(Self(bytes))
+    }
+
+    /// parses a key from hex, with or without the 0x prefix
+    pub fn from_hex(hex: &str) -> Result<Self, PrivateKeyError> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let mut bytes = hex::decode(hex).map_err(|_| PrivateKeyError::InvalidHex)?;
+
+        let key = Self::from_bytes(&bytes);
+        bytes.zeroize();
+        key
+    }
+
+    /// the key itself, use this instead of copying it so no unwiped copies are left behind
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// a copy of the key that is wiped when it's dropped
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.0)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G_TABLE.mul_secret(&self.scalar()))
+    }
+
+    /// Diffie-Hellman with a peer's public key, see `SharedSecret`
+    pub fn diffie_hellman(&self, peer: &PublicKey) -> SharedSecret {
+        SharedSecret::new(self, peer)
+    }
+
+    /// the key as a number, for doing arithmetic with it
+    pub(crate) fn scalar(&self) -> Scalar {
+        Scalar::from_bytes_be_reduced(&self.0)
+    }
+}
+
+impl Clone for PrivateKey {
+    fn clone(&self) -> Self {
+        Self(self.0)
+    }
+}
+
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        // compare every byte so the time taken doesn't depend on where the keys differ
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+}
+
+impl Eq for PrivateKey {}
+
+impl Debug for PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the key itself, e.g. in panic messages
+        write!(f, "PrivateKey(..)")
+    }
+}
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicKeyError {
+    /// public keys are 33 (compressed) or 65 (uncompressed) bytes long
+    InvalidLength(usize),
+    /// the first byte must be 0x02, 0x03 or 0x04
+    InvalidPrefix(u8),
+    InvalidHex,
+    /// a coordinate is not smaller than P
+    CoordinateOutOfRange,
+    NotOnCurve,
+    /// the point at infinity can't be used as a public key
+    Infinity,
+}
+
+impl Display for PublicKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicKeyError::InvalidLength(len) => write!(f, "invalid public key length {}", len),
+            PublicKeyError::InvalidPrefix(prefix) => {
+                write!(f, "invalid public key prefix 0x{:02x}", prefix)
+            }
+            PublicKeyError::InvalidHex => write!(f, "public key is not valid hex"),
+            PublicKeyError::CoordinateOutOfRange => write!(f, "coordinate is not less than P"),
+            PublicKeyError::NotOnCurve => write!(f, "point is not on the curve"),
+            PublicKeyError::Infinity => write!(f, "point is at infinity"),
+        }
+    }
+}
+
+impl std::error::Error for PublicKeyError {}
+
+/// a secp256k1 public key, always a point on the curve other than infinity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey(Point);
+
+impl PublicKey {
+    pub fn from_point(point: Point) -> Result<Self, PublicKeyError> {
+        if point.is_infinity() {
+            return Err(PublicKeyError::Infinity);
+        }
+
+        if !point.is_on_curve() {
+            return Err(PublicKeyError::NotOnCurve);
+        }
+
+        Ok(Self(point))
+    }
+
+    /// parses a SEC1 encoded public key (the output of `to_bytes_compressed`
+    /// or `to_bytes_uncompressed`) and checks that it's a valid point on the curve
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PublicKeyError> {
+        // SEC1 encodes the point at infinity as a single zero byte
+        if bytes == [0u8] {
+            return Err(PublicKeyError::Infinity);
+        }
+
+        let x = match bytes.len() {
+            33 | 65 => coordinate(&bytes[1..33])?,
+            len => return Err(PublicKeyError::InvalidLength(len)),
+        };
+
+        let point = match (bytes[0], bytes.len()) {
+            (4, 65) => Point::new(x, coordinate(&bytes[33..65])?),
+            // the prefix gives the parity of y
+            (prefix @ (2 | 3), 33) => {
+                Point::from_x(x, prefix == 3).ok_or(PublicKeyError::NotOnCurve)?
+            }
+            (prefix, _) => return Err(PublicKeyError::InvalidPrefix(prefix)),
+        };
+
+        Self::from_point(point)
+    }
+
+    /// parses a SEC1 encoded public key from hex, with or without the 0x prefix
+    pub fn from_hex(hex: &str) -> Result<Self, PublicKeyError> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let bytes = hex::decode(hex).map_err(|_| PublicKeyError::InvalidHex)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// wraps a point we already know to be valid (e.g. computed from a private key)
+    pub(crate) fn from_point_unchecked(point: Point) -> Self {
+        Self(point)
+    }
+
+    pub fn point(&self) -> &Point {
+        &self.0
+    }
+
+    pub fn to_bytes_uncompressed(&self) -> [u8; 65] {
+        // we start with 0x04 to indicate uncompressed format
+        let mut result = [4u8; 65];
+        result[1..33].copy_from_slice(&self.0.x.to_bytes_be());
+        result[33..].copy_from_slice(&self.0.y.to_bytes_be());
+        result
+    }
+
+    pub fn to_bytes_compressed(&self) -> [u8; 33] {
+        let mut result = [2u8; 33];
+        if self.0.y.is_odd() {
+            result[0] = 3;
+        }
+        result[1..].copy_from_slice(&self.0.x.to_bytes_be());
+        result
+    }
+
+    /// hex of the uncompressed encoding, without the 0x prefix
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes_uncompressed())
+    }
+
+    /// the last 20 bytes of the keccak256 hash of the uncompressed key (without the 0x04 prefix)
+    pub fn address(&self) -> Address {
+        Address::from(self.0)
+    }
+}
+
+/// a 32 byte big endian coordinate, which has to be smaller than P
+fn coordinate(bytes: &[u8]) -> Result<Fp, PublicKeyError> {
+    let bytes: &[u8; 32] = bytes.try_into().expect("coordinates are 32 bytes");
+    Fp::from_bytes_be(bytes).ok_or(PublicKeyError::CoordinateOutOfRange)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigInt;
+
+    use crate::{
+        ecdsa::curve::{G, O, P},
+        util::{bigint_from_hex, bigint_to_bytes32},
+    };
+
+    use super::*;
+
+    #[test]
+    fn private_key_range() {
+        assert_eq!(
+            PrivateKey::from_bytes(&[0u8; 32]),
+            Err(PrivateKeyError::OutOfRange)
+        );
+        assert_eq!(
+            PrivateKey::from_bytes(&bigint_to_bytes32(&O)),
+            Err(PrivateKeyError::OutOfRange)
+        );
+        assert_eq!(
+            PrivateKey::from_bytes(&[1u8; 31]),
+            Err(PrivateKeyError::InvalidLength(31))
+        );
+        assert!(PrivateKey::from_bytes(&bigint_to_bytes32(&(O.clone() - 1))).is_ok());
+        assert!(PrivateKey::from_bytes(&bigint_to_bytes32(&BigInt::from(1))).is_ok());
+    }
+
+    #[test]
+    fn private_key_hex() {
+        let hex = "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93";
+        let key = PrivateKey::from_hex(hex).unwrap();
+
+        assert_eq!(key.to_hex(), hex);
+        assert_eq!(PrivateKey::from_hex(&format!("0x{}", hex)), Ok(key.clone()));
+        assert_eq!(
+            PrivateKey::from_bytes(key.as_bytes())
+                .unwrap()
+                .scalar()
+                .to_bigint(),
+            bigint_from_hex(hex).unwrap()
+        );
+        assert_eq!(
+            PrivateKey::from_hex("xyz"),
+            Err(PrivateKeyError::InvalidHex)
+        );
+    }
+
+    #[test]
+    fn private_key_random() {
+        let key1 = PrivateKey::random();
+        let key2 = PrivateKey::random();
+
+        assert_ne!(key1, key2);
+        assert_eq!(PrivateKey::from_bytes(key1.as_bytes()), Ok(key1));
+    }
+
+    #[test]
+    fn private_key_debug_is_redacted() {
+        let key = PrivateKey::from_hex(
+            "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93",
+        )
+        .unwrap();
+
+        assert_eq!(format!("{:?}", key), "PrivateKey(..)");
+    }
+
+    #[test]
+    fn get_pubkey_uncompressed() {
+        let private_key = PrivateKey::from_hex(
+            "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93",
+        )
+        .unwrap();
+
+        assert_eq!(
+            private_key.public_key().to_hex(),
+            "0476abf7ad93d73818541bb7c5e28fa011e2935f5bf507591693da8594efd23a2925e325adae63c1111224e964d5b86d32027b61429ea155adf9edb84e6bb3fd46"
+        )
+    }
+
+    #[test]
+    fn get_pubkey_compressed_even() {
+        let private_key = PrivateKey::from_hex(
+            "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(private_key.public_key().to_bytes_compressed()),
+            "0276abf7ad93d73818541bb7c5e28fa011e2935f5bf507591693da8594efd23a29"
+        )
+    }
+
+    #[test]
+    fn get_pubkey_compressed_odd() {
+        let private_key = PrivateKey::from_hex(
+            "7b2f17cf50ef33bcb8b404d718b2e1fde3f2d025fe34f8d3f4c6e526e447ef13",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(private_key.public_key().to_bytes_compressed()),
+            "0314397848a6600eee675f59fb7829917a30c6dca7f1e7c82bdffbb7774978fe98"
+        )
+    }
+
+    #[test]
+    fn decode_pubkey_roundtrip() {
+        for hex in [
+            "b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93",
+            "7b2f17cf50ef33bcb8b404d718b2e1fde3f2d025fe34f8d3f4c6e526e447ef13",
+        ] {
+            let public_key = PrivateKey::from_hex(hex).unwrap().public_key();
+
+            let compressed = public_key.to_bytes_compressed();
+            let uncompressed = public_key.to_bytes_uncompressed();
+
+            assert_eq!(PublicKey::from_bytes(&compressed), Ok(public_key.clone()));
+            assert_eq!(PublicKey::from_bytes(&uncompressed), Ok(public_key.clone()));
+            assert_eq!(PublicKey::from_hex(&public_key.to_hex()), Ok(public_key));
+        }
+    }
+
+    #[test]
+    fn encode_pubkey_leading_zeros() {
+        // 153 * G has an x coordinate with a leading zero byte, 122 * G has such a y coordinate
+        let x_key = PublicKey::from_point(G.mul(&Scalar::from_u64(153))).unwrap();
+        let y_key = PublicKey::from_point(G.mul(&Scalar::from_u64(122))).unwrap();
+
+        assert_eq!(
+            hex::encode(x_key.to_bytes_compressed()),
+            "0200e3ae1974566ca06cc516d47e0fb165a674a3dabcfca15e722f0e3450f45889"
+        );
+        assert_eq!(
+            y_key.to_hex(),
+            "04139ae46a1133f1f9d23f25efba0f6dd87bf7ddaf568a5fb9e0a3bfda7317623700995e555c8aabd263fd238833a12188b8a5ffbeb480ba0e3e6ec481a8991472"
+        );
+
+        for key in [x_key, y_key] {
+            assert_eq!(
+                PublicKey::from_bytes(&key.to_bytes_compressed()),
+                Ok(key.clone())
+            );
+            assert_eq!(PublicKey::from_bytes(&key.to_bytes_uncompressed()), Ok(key));
+        }
+    }
+
+    #[test]
+    fn decode_pubkey_invalid() {
+        let mut uncompressed = PublicKey::from_point(*G).unwrap().to_bytes_uncompressed();
+
+        assert_eq!(
+            PublicKey::from_bytes(&uncompressed[..64]),
+            Err(PublicKeyError::InvalidLength(64))
+        );
+        assert_eq!(PublicKey::from_bytes(&[0]), Err(PublicKeyError::Infinity));
+        assert_eq!(
+            PublicKey::from_bytes(&[&[4u8][..], &[0u8; 64][..]].concat()),
+            Err(PublicKeyError::Infinity)
+        );
+        assert_eq!(
+            PublicKey::from_hex("0x04zz"),
+            Err(PublicKeyError::InvalidHex)
+        );
+
+        uncompressed[0] = 2;
+        assert_eq!(
+            PublicKey::from_bytes(&uncompressed),
+            Err(PublicKeyError::InvalidPrefix(2))
+        );
+
+        uncompressed[0] = 4;
+        uncompressed[64] ^= 1;
+        assert_eq!(
+            PublicKey::from_bytes(&uncompressed),
+            Err(PublicKeyError::NotOnCurve)
+        );
+
+        // x = P is out of range
+        let mut compressed = vec![2u8];
+        compressed.extend_from_slice(&bigint_to_bytes32(&P));
+        assert_eq!(
+            PublicKey::from_bytes(&compressed),
+            Err(PublicKeyError::CoordinateOutOfRange)
+        );
+
+        // x = 5 is not on the curve, since 5^3 + 7 is not a square mod P
+        let mut compressed = vec![3u8];
+        compressed.extend_from_slice(&bigint_to_bytes32(&BigInt::from(5)));
+        assert_eq!(
+            PublicKey::from_bytes(&compressed),
+            Err(PublicKeyError::NotOnCurve)
+        );
+    }
+
+    #[test]
+    fn address_from_public_key() {
+        let private_key = PrivateKey::from_hex(
+            "4646464646464646464646464646464646464646464646464646464646464646",
+        )
+        .unwrap();
+
+        assert_eq!(
+            private_key.public_key().address().to_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+    }
+}
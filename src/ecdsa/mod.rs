@@ -1,346 +1,851 @@
-use crate::{
-    address::Address,
-    ecdsa::curve::{Point, O, P},
-    keccak::keccak256,
-};
-use num_bigint::{BigInt, BigUint, RandBigInt};
+use crate::{address::Address, ecdsa::curve::O};
+use std::fmt::Display;
+
+use num_bigint::{BigInt, Sign};
+use once_cell::sync::Lazy;
 
 use self::{
-    curve::G,
-    number_theory::{mod_inverse, mod_sqrt, modulo},
+    curve::G_TABLE,
+    field::{add_raw, lt_raw, Fp, PrimeField, Secp256k1Field, Secp256k1Scalar},
+    rfc6979::NonceGenerator,
 };
 
+pub use self::batch::{
+    ecrecover_batch, ecrecover_batch_parallel, recover_public_keys_batch, verify_batch,
+    verify_batch_parallel,
+};
+pub use self::curve::{AffinePoint, Curve, Point, Secp256k1};
+pub use self::ecdh::SharedSecret;
+pub use self::field::Scalar;
+pub use self::keys::{PrivateKey, PrivateKeyError, PublicKey, PublicKeyError};
+pub use self::p256::{p256_verify, P256PublicKey, P256Signature, P256};
+
+mod batch;
 mod curve;
+mod ecdh;
+mod field;
+mod keys;
 mod number_theory;
-
-/// **NOTE: This is not cryptographically secure, only for illustrative purposes**
-pub fn gen_random_private_key() -> BigInt {
-    let mut rand = rand::thread_rng();
-    rand.gen_bigint_range(&BigInt::from(0), &curve::O)
+mod p256;
+mod rfc6979;
+
+/// half of the curve order, signatures with s above this are malleable (EIP-2)
+static HALF_O: Lazy<Scalar> = Lazy::new(|| Scalar::from_bigint(&(O.clone() / 2)));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryError {
+    /// v is neither 27..=30 nor a valid EIP-155 value, holds the v value
+    InvalidRecoveryId(u64),
+    /// r is zero, or r + O is not a valid x coordinate for recovery ids 2 and 3
+    ROutOfRange,
+    /// s is zero
+    SOutOfRange,
+    /// x^3 + 7 has no square root, so there is no point with r as its x coordinate
+    NonResidue,
+    /// the recovered public key would be the point at infinity
+    PointAtInfinity,
 }
 
-pub fn get_public_key(private_key: &BigInt) -> curve::Point {
-    G.mul(private_key)
+impl Display for RecoveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecoveryError::InvalidRecoveryId(v) => write!(f, "invalid recovery id, v = {}", v),
+            RecoveryError::ROutOfRange => write!(f, "r is out of range"),
+            RecoveryError::SOutOfRange => write!(f, "s is out of range"),
+            RecoveryError::NonResidue => write!(f, "r is not the x coordinate of a curve point"),
+            RecoveryError::PointAtInfinity => write!(f, "recovered point is at infinity"),
+        }
+    }
 }
 
-pub fn encode_public_key_uncompressed(public_key: &curve::Point) -> Vec<u8> {
-    // we start with 0x04 to indicate uncompressed format
-    let mut result = vec![4u8];
+impl std::error::Error for RecoveryError {}
 
-    let mut x_part = public_key.x.to_bytes_be().1;
-    let mut y_part = public_key.y.to_bytes_be().1;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub r: Scalar,
+    pub s: Scalar,
+    /// 27 + recovery id for legacy signatures, chain_id * 2 + 35 + recovery id for EIP-155
+    pub v: u64,
+}
 
-    while x_part.len() < 32 {
-        x_part.push(0u8);
+/// Signs a message with a private key
+impl Signature {
+    /// signs a hash with a deterministic RFC 6979 nonce, so the same key and hash
+    /// always produce the same signature
+    pub fn create(private_key: &PrivateKey, hash: [u8; 32]) -> Self {
+        Self::sign(private_key, hash, None)
     }
 
-    while y_part.len() < 32 {
-        y_part.push(0u8);
+    /// like `create`, but mixes additional data into the nonce derivation (RFC 6979 section 3.6)
+    /// this is still deterministic for a given `extra_entropy`, pass random bytes to get
+    /// randomized signatures that don't rely on the RNG for security
+    pub fn create_with_extra_entropy(
+        private_key: &PrivateKey,
+        hash: [u8; 32],
+        extra_entropy: &[u8; 32],
+    ) -> Self {
+        Self::sign(private_key, hash, Some(extra_entropy))
     }
 
-    result.extend_from_slice(&x_part);
-    result.extend_from_slice(&y_part);
-    result
-}
+    fn sign(private_key: &PrivateKey, hash: [u8; 32], extra_entropy: Option<&[u8; 32]>) -> Self {
+        let mut nonces = NonceGenerator::new(private_key.as_bytes(), &hash, extra_entropy);
+        let z = Scalar::from_bytes_be_reduced(&hash);
+        let private_key = private_key.scalar();
 
-pub fn encode_public_key_compressed(public_key: &curve::Point) -> Vec<u8> {
-    let mut result = if public_key.clone().y % 2 == BigInt::from(1) {
-        vec![3u8]
-    } else {
-        vec![2u8]
-    };
-    result.extend_from_slice(&public_key.x.to_bytes_be().1);
-    result
-}
+        loop {
+            let k = nonces.next_nonce();
+            let r_point = G_TABLE.mul_secret(&k);
 
-pub fn get_address(public_key: &curve::Point) -> Address {
-    let public_key: Vec<u8> = encode_public_key_uncompressed(public_key)
-        .into_iter()
-        .skip(1)
-        .collect();
-    let hash: Vec<u8> = keccak256(&public_key).into_iter().skip(12).collect();
-    Address::from(BigUint::from_bytes_be(&hash))
-}
+            let x = r_point.x.to_bytes_be();
+            let r = Scalar::from_bytes_be_reduced(&x);
+            if r.is_zero() {
+                continue;
+            }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Signature {
-    pub r: BigInt,
-    pub s: BigInt,
-    pub v: u8,
-}
+            let s = (z + private_key * r) * k.invert();
+            if s.is_zero() {
+                continue;
+            }
 
-/// Signs a message with a private key
-impl Signature {
-    pub fn create(private_key: &BigInt, hash: [u8; 32]) -> Self {
-        let mut rand = rand::thread_rng();
+            // if x was reduced the recovered point has to add O back to r
+            let x_below_o = r.to_bytes_be() == x;
 
-        let mut k = BigInt::default();
-        let mut r_point = Point::infinity();
+            let recovery_id = match (!r_point.y.is_odd(), x_below_o) {
+                (true, true) => 0,
+                (false, true) => 1,
+                (true, false) => 2,
+                (false, false) => 3,
+            };
 
-        while r_point.x == BigInt::default() {
-            k = rand.gen_bigint_range(&BigInt::from(0), &curve::O);
-            r_point = G.mul(&k);
+            // only produce low s values, see `normalize_s`
+            let (s, recovery_id) = if s <= *HALF_O {
+                (s, recovery_id)
+            } else {
+                (-s, recovery_id ^ 1)
+            };
+
+            return Signature {
+                r,
+                s,
+                v: recovery_id + 27,
+            };
         }
+    }
 
-        let r = modulo(&r_point.x, &O);
+    /// signs a hash with EIP-155 replay protection, the chain id is encoded in v
+    /// https://eips.ethereum.org/EIPS/eip-155
+    ///
+    /// `None` if v = chain_id * 2 + 35 + recovery_id doesn't fit into a u64
+    pub fn create_with_chain_id(
+        private_key: &PrivateKey,
+        hash: [u8; 32],
+        chain_id: u64,
+    ) -> Option<Self> {
+        let signature = Signature::create(private_key, hash);
+        let recovery_id = signature.v - 27;
+
+        Some(Self {
+            r: signature.r,
+            s: signature.s,
+            v: eip155_v(chain_id, recovery_id)?,
+        })
+    }
 
-        let hash = BigInt::from_bytes_be(num_bigint::Sign::Plus, &hash);
+    /// the recovery id encoded in v, for both legacy and EIP-155 signatures
+    pub fn recovery_id(&self) -> Option<u8> {
+        match self.v {
+            27..=30 => Some((self.v - 27) as u8),
+            35.. => Some(((self.v - 35) % 2) as u8),
+            _ => None,
+        }
+    }
 
-        let s = (hash + private_key * r.clone()) * mod_inverse(&k, &O);
-        let s = modulo(&s, &O);
+    /// the chain id of an EIP-155 signature, `None` for legacy signatures
+    pub fn chain_id(&self) -> Option<u64> {
+        match self.v {
+            35.. => Some((self.v - 35) / 2),
+            _ => None,
+        }
+    }
 
-        let recovery_id = if r_point.y % 2 == BigInt::from(0) {
-            if r_point.x < O.clone() {
-                0
-            } else {
-                2
-            }
-        } else {
-            if r_point.x < O.clone() {
-                1
-            } else {
-                3
-            }
+    /// whether s is in the lower half of the curve order, as required by EIP-2
+    pub fn is_low_s(&self) -> bool {
+        self.s <= *HALF_O
+    }
+
+    /// (r, s) and (r, O - s) are both valid signatures for the same hash,
+    /// this picks the one with the low s value and flips the recovery id to match
+    /// (negating s negates the recovered R point, which flips the parity of its y coordinate)
+    ///
+    /// signatures whose v doesn't hold a recovery id have no parity to flip,
+    /// so they are returned unchanged, `None` if the flipped EIP-155 v doesn't fit into a u64
+    pub fn normalize_s(&self) -> Option<Self> {
+        let recovery_id = match self.recovery_id() {
+            Some(recovery_id) if !self.is_low_s() => recovery_id,
+            _ => return Some(self.clone()),
         };
 
-        Signature {
-            r,
-            s,
-            v: recovery_id + 27,
-        }
-    }
+        let flipped = (recovery_id ^ 1) as u64;
+        let v = match self.chain_id() {
+            Some(chain_id) => eip155_v(chain_id, flipped)?,
+            None => 27 + flipped,
+        };
 
-    // TODO: implement this and create proper verification
-    // pub fn create_with_chain_id(private_key: &BigInt, message: &[u8], chain_id: u8) -> Self {
-    //     let signature = Signature::create(private_key, message);
-    //     let recovery_id = signature.v - 27;
-    //     let v = chain_id * 2 + 35 + recovery_id;
+        Some(Self {
+            r: self.r,
+            s: -self.s,
+            v,
+        })
+    }
 
-    //     Self {
-    //         r: signature.r,
-    //         s: signature.s,
-    //         v,
-    //     }
-    // }
+    /// like `verify`, but also rejects malleable signatures with a high s value (EIP-2)
+    /// this matches the rules for transaction signatures since the Homestead fork
+    pub fn verify_strict(&self, hash: &[u8], public_key: &PublicKey) -> bool {
+        self.is_low_s() && self.verify(hash, public_key)
+    }
 
-    pub fn verify(&self, hash: &[u8], public_key: &curve::Point) -> bool {
-        // reject invalid values for parameters
-        if self.r < BigInt::from(1) || self.r > O.clone() {
+    /// verifies the signature, accepting any s in [1, O)
+    /// use `verify_strict` to also reject malleable high s signatures
+    pub fn verify(&self, hash: &[u8], public_key: &PublicKey) -> bool {
+        // reject invalid values for parameters, r and s are always below O
+        if self.r.is_zero() || self.s.is_zero() {
             return false;
         }
-        if self.s < BigInt::from(1) || self.s > O.clone() {
+        if self.recovery_id().is_none() {
             return false;
         }
-        if self.v < 27 || self.v > 30 {
+
+        let hash = hash_to_scalar(hash);
+
+        let w = self.s.invert();
+        let u1 = hash * w;
+        let u2 = self.r * w;
+
+        let r_point = G_TABLE.mul_add(&u1, public_key.point(), &u2).to_affine();
+
+        if r_point.is_infinity() {
             return false;
         }
 
-        let hash = BigInt::from_bytes_be(num_bigint::Sign::Plus, &hash);
+        Scalar::from_bytes_be_reduced(&r_point.x.to_bytes_be()) == self.r
+    }
 
-        let w = mod_inverse(&self.s, &O);
-        let u1 = hash * w.clone();
-        let u2 = self.r.clone() * w;
+    /// recovers the public key that created the signature, this never panics,
+    /// invalid signatures (e.g. from untrusted transactions) give an error
+    pub fn recover_public_key(&self, hash: &[u8]) -> Result<PublicKey, RecoveryError> {
+        let r_point = self.recovery_point()?;
+        let hash = hash_to_scalar(hash);
 
-        let p1 = G.mul(&u1);
-        let p2 = public_key.mul(&u2);
+        // r_point.x is r or r + O, which are the same mod O
+        let r_inv = self.r.invert();
+        let u1 = -hash * r_inv;
+        let u2 = self.s * r_inv;
 
-        let mut r_point = p1.add(&p2);
+        let point = G_TABLE.mul_add(&u1, &r_point, &u2).to_affine();
+        if point.is_infinity() {
+            return Err(RecoveryError::PointAtInfinity);
+        }
 
-        if r_point.x == BigInt::default() {
-            return false;
+        Ok(PublicKey::from_point_unchecked(point))
+    }
+
+    /// the point R whose x coordinate produced r, as selected by the recovery id
+    /// also checks that r and s are in the range [1, O)
+    fn recovery_point(&self) -> Result<Point, RecoveryError> {
+        let recovery_id = self
+            .recovery_id()
+            .ok_or(RecoveryError::InvalidRecoveryId(self.v))?;
+
+        // r and s are always below O, but zero is not allowed either
+        if self.r.is_zero() {
+            return Err(RecoveryError::ROutOfRange);
+        }
+        if self.s.is_zero() {
+            return Err(RecoveryError::SOutOfRange);
         }
 
-        r_point.x = modulo(&r_point.x, &O);
+        let mut x = self.r.to_raw();
+
+        // recovery ids 2 and 3 mean the x coordinate was at least O and got reduced
+        if recovery_id > 1 {
+            let (sum, carry) = add_raw(&x, &Secp256k1Scalar::MODULUS);
+            if carry == 1 || !lt_raw(&sum, &Secp256k1Field::MODULUS) {
+                return Err(RecoveryError::ROutOfRange);
+            }
+            x = sum;
+        }
 
-        r_point.x == self.r
+        // the recovery id gives the parity of y
+        Point::from_x(Fp::from_raw(x), recovery_id % 2 == 1).ok_or(RecoveryError::NonResidue)
     }
 
-    pub fn recover_public_key(&self, hash: &[u8]) -> Point {
-        let hash = BigInt::from_bytes_be(num_bigint::Sign::Plus, &hash);
+    pub fn ecrecover(&self, hash: &[u8]) -> Result<Address, RecoveryError> {
+        Ok(self.recover_public_key(hash)?.address())
+    }
 
-        let recovery_id = self.v - 27;
+    /// r || s || v, `None` if v doesn't hold a recovery id
+    pub fn to_signature_bytes(&self) -> Option<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(65);
 
-        let mut x = self.r.clone();
-        let y;
+        // there's only room for a single v byte, so EIP-155 signatures are
+        // written with the legacy v = 27 + recovery id
+        let v = 27 + self.recovery_id()?;
 
-        let is_even = recovery_id % 2 == 0;
-        let is_over_o = recovery_id > 1;
+        bytes.extend_from_slice(&self.r.to_bytes_be());
+        bytes.extend_from_slice(&self.s.to_bytes_be());
+        bytes.push(v);
 
-        if is_over_o {
-            x += O.clone();
-        }
+        Some(bytes)
+    }
 
-        let y2 = x.clone() * x.clone() * x.clone() + BigInt::from(7);
-        let y_option_1 = mod_sqrt(&y2, &P).unwrap();
-        let y_option_2 = P.clone() - y_option_1.clone();
-
-        if is_even && y_option_1.clone() % 2 == BigInt::from(0) {
-            y = y_option_1;
-        } else if is_even && y_option_2.clone() % 2 == BigInt::from(0) {
-            y = y_option_2;
-        } else if !is_even && y_option_1.clone() % 2 == BigInt::from(1) {
-            y = y_option_1;
-        } else if !is_even && y_option_2.clone() % 2 == BigInt::from(1) {
-            y = y_option_2;
-        } else {
-            println!("Could not find y");
-            y = y_option_2; // i guess
+    /// parses r || s || v, returns `None` if the length is wrong, r or s are not in [1, O)
+    /// or v is neither 27..=30 nor a bare recovery id 0..=3 (as some hardware wallets return),
+    /// bare recovery ids are converted to 27 + recovery id
+    pub fn from_signature_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 65 {
+            return None;
         }
 
-        let r_point = Point::new(&x, &y);
+        let (r, s) = scalars_from_bytes(bytes[0..64].try_into().unwrap())?;
+        let v = match bytes[64] {
+            v @ 0..=3 => v + 27,
+            v @ 27..=30 => v,
+            _ => return None,
+        };
 
-        let u1 = modulo(&(-hash * mod_inverse(&r_point.x, &O)), &O);
-        let u2 = modulo(&(&self.s * mod_inverse(&r_point.x, &O)), &O);
+        Some(Self { r, s, v: v.into() })
+    }
 
-        let p1 = G.mul(&u1);
-        let p2 = r_point.mul(&u2);
+    /// the 64 byte EIP-2098 encoding r || (y parity << 255 | s)
+    /// https://eips.ethereum.org/EIPS/eip-2098
+    ///
+    /// like `to_signature_bytes` this drops the chain id of EIP-155 signatures, returns `None`
+    /// for signatures that can't be encoded: high s values and recovery ids 2 and 3
+    pub fn to_compact_bytes(&self) -> Option<[u8; 64]> {
+        let recovery_id = self.recovery_id()?;
+        if !self.is_low_s() || recovery_id > 1 {
+            return None;
+        }
 
-        p1.add(&p2)
-    }
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r.to_bytes_be());
+        bytes[32..].copy_from_slice(&self.s.to_bytes_be());
+        // s is in the lower half of the order, so its top bit is always free
+        bytes[32] |= recovery_id << 7;
 
-    pub fn ecrecover(&self, hash: &[u8]) -> Address {
-        let public_key = self.recover_public_key(hash);
-        get_address(&public_key)
+        Some(bytes)
     }
 
-    pub fn to_signature_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![];
+    /// parses an EIP-2098 compact signature, returns `None` if the length is wrong,
+    /// r or s are zero or not smaller than O, or s is not in the lower half of the order
+    pub fn from_compact_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut bytes: [u8; 64] = bytes.try_into().ok()?;
 
-        let mut r_bytes = self.r.to_bytes_be().1;
-        let mut s_bytes = self.s.to_bytes_be().1;
+        let y_parity = bytes[32] >> 7;
+        bytes[32] &= 0x7f;
 
-        // ensure that the signature is 65 bytes long
-        if r_bytes.len() < 32 {
-            let mut new_bytes = vec![0; 32 - r_bytes.len()];
-            new_bytes.append(&mut r_bytes);
-            r_bytes = new_bytes;
-        }
+        let (r, s) = scalars_from_bytes(&bytes)?;
+        let signature = Self {
+            r,
+            s,
+            v: 27 + y_parity as u64,
+        };
 
-        if s_bytes.len() < 32 {
-            let mut new_bytes = vec![0; 32 - s_bytes.len()];
-            new_bytes.append(&mut s_bytes);
-            s_bytes = new_bytes;
+        // a high s would have a different compact encoding after a round trip
+        if signature.is_low_s() {
+            Some(signature)
+        } else {
+            None
         }
+    }
+
+    /// the ASN.1 DER encoding SEQUENCE { r INTEGER, s INTEGER } used outside of Ethereum,
+    /// e.g. by Bitcoin and OpenSSL, it has no room for the recovery id
+    pub fn to_der(&self) -> Vec<u8> {
+        let r = der_integer(&self.r);
+        let s = der_integer(&self.s);
 
-        bytes.append(&mut r_bytes);
-        bytes.append(&mut s_bytes);
-        bytes.push(self.v);
+        let mut bytes = Vec::with_capacity(2 + r.len() + s.len());
+        bytes.push(0x30);
+        bytes.push((r.len() + s.len()) as u8);
+        bytes.extend_from_slice(&r);
+        bytes.extend_from_slice(&s);
 
         bytes
     }
 
-    pub fn from_signature_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 65 {
+    /// parses a strict DER signature (as in BIP-66), DER doesn't contain the recovery id
+    /// so it has to be given, any of them will do if the signature is only verified
+    pub fn from_der(bytes: &[u8], recovery_id: u8) -> Option<Self> {
+        if recovery_id > 3 {
+            return None;
+        }
+
+        // the sequence tag and length, both integers take at least 3 bytes
+        if bytes.len() < 8 || bytes[0] != 0x30 || bytes[1] as usize != bytes.len() - 2 {
             return None;
         }
 
-        let r = BigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes[0..32]);
-        let s = BigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes[32..64]);
-        let v = bytes[64];
+        let (r, rest) = parse_der_integer(&bytes[2..])?;
+        let (s, rest) = parse_der_integer(rest)?;
+        if !rest.is_empty() {
+            return None;
+        }
+
+        let mut raw = [0u8; 64];
+        raw[32 - r.len()..32].copy_from_slice(r);
+        raw[64 - s.len()..].copy_from_slice(s);
+        let (r, s) = scalars_from_bytes(&raw)?;
+
+        Some(Self {
+            r,
+            s,
+            v: 27 + recovery_id as u64,
+        })
+    }
+}
+
+/// parses big endian r || s, both have to be in [1, O)
+/// v = chain_id * 2 + 35 + recovery_id, `None` if it doesn't fit into a u64
+fn eip155_v(chain_id: u64, recovery_id: u64) -> Option<u64> {
+    chain_id.checked_mul(2)?.checked_add(35 + recovery_id)
+}
+
+fn scalars_from_bytes(bytes: &[u8; 64]) -> Option<(Scalar, Scalar)> {
+    let r = Scalar::from_bytes_be(bytes[0..32].try_into().unwrap())?;
+    let s = Scalar::from_bytes_be(bytes[32..64].try_into().unwrap())?;
 
-        Some(Self { r, s, v })
+    if r.is_zero() || s.is_zero() {
+        return None;
+    }
+
+    Some((r, s))
+}
+
+/// a DER INTEGER, the shortest big endian encoding with a zero byte in front
+/// if the top bit is set, since the numbers are signed
+fn der_integer(scalar: &Scalar) -> Vec<u8> {
+    let bytes = scalar.to_bytes_be();
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(31);
+
+    let mut integer = vec![0x02, 0];
+    if bytes[start] & 0x80 != 0 {
+        integer.push(0);
+    }
+    integer.extend_from_slice(&bytes[start..]);
+    integer[1] = (integer.len() - 2) as u8;
+
+    integer
+}
+
+/// parses a positive DER INTEGER of at most 32 bytes without its padding,
+/// returns the big endian value and the remaining input
+fn parse_der_integer(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    if bytes.len() < 3 || bytes[0] != 0x02 {
+        return None;
+    }
+
+    let len = bytes[1] as usize;
+    if bytes.len() < 2 + len {
+        return None;
+    }
+    let (value, rest) = bytes[2..].split_at(len);
+
+    // negative numbers and leading zeros that aren't needed for the sign are not allowed
+    if len == 0 || value[0] & 0x80 != 0 || (len > 1 && value[0] == 0 && value[1] & 0x80 == 0) {
+        return None;
+    }
+
+    let value = if value[0] == 0 && len > 1 {
+        &value[1..]
+    } else {
+        value
+    };
+
+    if value.len() > 32 {
+        return None;
+    }
+
+    Some((value, rest))
+}
+
+/// the message hash as a scalar, hashes are normally 32 bytes but any length is accepted
+fn hash_to_scalar(hash: &[u8]) -> Scalar {
+    match hash.try_into() {
+        Ok(hash) => Scalar::from_bytes_be_reduced(hash),
+        Err(_) => Scalar::from_bigint(&BigInt::from_bytes_be(Sign::Plus, hash)),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::bigint_from_hex;
+    use crate::{ecdsa::curve::G, keccak::keccak256};
 
     use super::*;
 
     #[test]
-    fn get_pubkey_uncompressed() {
-        let private_key = BigInt::from_bytes_be(
-            num_bigint::Sign::Plus,
-            &hex::decode("b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93")
-                .unwrap(),
-        );
-        let public_key = get_public_key(&private_key);
-
-        assert_eq!(
-            hex::encode(encode_public_key_uncompressed(&public_key)),
-            "0476abf7ad93d73818541bb7c5e28fa011e2935f5bf507591693da8594efd23a2925e325adae63c1111224e964d5b86d32027b61429ea155adf9edb84e6bb3fd46"
+    fn sign_ecrecover_self() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
         )
+        .unwrap();
+        let address = private_key.public_key().address();
+
+        let message = "hello world";
+        let hash = keccak256(message.as_bytes());
+
+        let signature = Signature::create(&private_key, hash);
+        let recovered_address = signature.ecrecover(&hash).unwrap();
+
+        assert_eq!(address, recovered_address);
     }
 
     #[test]
-    fn get_pubkey_compressed_even() {
-        let private_key = BigInt::from_bytes_be(
-            num_bigint::Sign::Plus,
-            &hex::decode("b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93")
-                .unwrap(),
-        );
-        let public_key = get_public_key(&private_key);
+    fn sign_deterministic() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let hash = keccak256("hello world".as_bytes());
 
         assert_eq!(
-            hex::encode(encode_public_key_compressed(&public_key)),
-            "0276abf7ad93d73818541bb7c5e28fa011e2935f5bf507591693da8594efd23a29"
-        )
+            Signature::create(&private_key, hash),
+            Signature::create(&private_key, hash)
+        );
     }
 
     #[test]
-    fn get_pubkey_compressed_odd() {
-        let private_key = BigInt::from_bytes_be(
-            num_bigint::Sign::Plus,
-            &hex::decode("7b2f17cf50ef33bcb8b404d718b2e1fde3f2d025fe34f8d3f4c6e526e447ef13")
-                .unwrap(),
+    fn sign_rfc6979_vector() {
+        // sha256("Satoshi Nakamoto") signed with private key 1
+        let hash = hex::decode("a0dc65ffca799873cbea0ac274015b9526505daaaed385155425f7337704883e")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signature = Signature::create(
+            &PrivateKey::from_hex(
+                "0000000000000000000000000000000000000000000000000000000000000001",
+            )
+            .unwrap(),
+            hash,
         );
-        let public_key = get_public_key(&private_key);
 
         assert_eq!(
-            hex::encode(encode_public_key_compressed(&public_key)),
-            "0314397848a6600eee675f59fb7829917a30c6dca7f1e7c82bdffbb7774978fe98"
-        )
+            signature.r,
+            Scalar::from_hex("934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8")
+                .unwrap()
+        );
+        assert_eq!(
+            signature.s,
+            Scalar::from_hex("2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5")
+                .unwrap()
+        );
+        assert_eq!(signature.v, 28);
     }
 
     #[test]
-    fn sign_ecrecover_self() {
-        let private_key =
-            bigint_from_hex("c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a")
-                .unwrap();
-        let public_key_point = get_public_key(&private_key);
-        let address = get_address(&public_key_point);
-
-        let message = "hello world";
-        let hash = keccak256(message.as_bytes());
+    fn sign_extra_entropy() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let public_key = private_key.public_key();
+        let hash = keccak256("hello world".as_bytes());
 
-        let signature = Signature::create(&private_key, hash);
-        let recovered_address = signature.ecrecover(&hash);
+        let plain = Signature::create(&private_key, hash);
+        let randomized = Signature::create_with_extra_entropy(&private_key, hash, &[42u8; 32]);
 
-        assert_eq!(address, recovered_address);
+        assert_ne!(plain, randomized);
+        assert!(randomized.verify(&hash, &public_key));
+        assert_eq!(randomized.ecrecover(&hash).unwrap(), public_key.address());
     }
 
     #[test]
     fn sign_ecrecover_ext() {
         let signature = Signature {
             v: 27,
-            r: bigint_from_hex("1556a70d76cc452ae54e83bb167a9041f0d062d000fa0dcb42593f77c544f647")
+            r: Scalar::from_hex("1556a70d76cc452ae54e83bb167a9041f0d062d000fa0dcb42593f77c544f647")
                 .unwrap(),
-            s: bigint_from_hex("1643d14dbd6a6edc658f4b16699a585181a08dba4f6d16a9273e0e2cbed622da")
+            s: Scalar::from_hex("1643d14dbd6a6edc658f4b16699a585181a08dba4f6d16a9273e0e2cbed622da")
                 .unwrap(),
         };
         let hash = hex::decode("3ea2f1d0abf3fc66cf29eebb70cbd4e7fe762ef8a09bcc06c8edf641230afec0")
             .unwrap();
 
-        let recovered_address = signature.ecrecover(&hash);
+        let recovered_address = signature.ecrecover(&hash).unwrap();
 
         assert_eq!(
             recovered_address.to_string(),
-            "0x80c67eec6f8518b5bb707ecc718b53782ac71543"
+            "0x80C67eEC6f8518B5Bb707ECc718B53782AC71543"
         )
     }
 
+    #[test]
+    fn sign_eip155_example() {
+        // the example transaction from EIP-155
+        let private_key = PrivateKey::from_hex(
+            "4646464646464646464646464646464646464646464646464646464646464646",
+        )
+        .unwrap();
+        let hash = hex::decode("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let signature = Signature::create_with_chain_id(&private_key, hash, 1).unwrap();
+
+        assert_eq!(signature.v, 37);
+        assert_eq!(
+            signature.r.to_bigint().to_string(),
+            "18515461264373351373200002665853028612451056578545711640558177340181847433846"
+        );
+        assert_eq!(
+            signature.s.to_bigint().to_string(),
+            "46948507304638947509940763649030358759909902576025900602547168820602576006531"
+        );
+        assert_eq!(signature.chain_id(), Some(1));
+        assert_eq!(signature.recovery_id(), Some(0));
+        assert_eq!(
+            signature.ecrecover(&hash).unwrap().to_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+    }
+
+    #[test]
+    fn sign_large_chain_ids() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let public_key = private_key.public_key();
+        let hash = keccak256("hello world".as_bytes());
+
+        let legacy = Signature::create(&private_key, hash);
+
+        // polygon and sepolia both need a v that doesn't fit into a byte
+        for chain_id in [137u64, 11155111] {
+            let signature = Signature::create_with_chain_id(&private_key, hash, chain_id).unwrap();
+
+            assert_eq!(signature.chain_id(), Some(chain_id));
+            assert_eq!(signature.recovery_id(), legacy.recovery_id());
+            assert_eq!(signature.v, chain_id * 2 + 35 + (legacy.v - 27));
+            assert!(signature.verify(&hash, &public_key));
+            assert_eq!(signature.ecrecover(&hash).unwrap(), public_key.address());
+            assert_eq!(signature.to_signature_bytes(), legacy.to_signature_bytes());
+        }
+
+        assert_eq!(legacy.chain_id(), None);
+
+        // the largest chain id whose v fits for both recovery ids
+        let chain_id = u64::MAX / 2 - 18;
+        let signature = Signature::create_with_chain_id(&private_key, hash, chain_id).unwrap();
+        assert_eq!(signature.chain_id(), Some(chain_id));
+        assert_eq!(signature.recovery_id(), legacy.recovery_id());
+        assert_eq!(signature.ecrecover(&hash).unwrap(), public_key.address());
+
+        assert_eq!(
+            Signature::create_with_chain_id(&private_key, hash, u64::MAX / 2),
+            None
+        );
+        assert_eq!(
+            Signature::create_with_chain_id(&private_key, hash, u64::MAX),
+            None
+        );
+    }
+
+    #[test]
+    fn verify_rejects_invalid_v() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let public_key = private_key.public_key();
+        let hash = keccak256("hello world".as_bytes());
+
+        let mut signature = Signature::create(&private_key, hash);
+
+        for v in [0, 26, 31, 34] {
+            signature.v = v;
+            assert_eq!(signature.recovery_id(), None);
+            assert!(!signature.verify(&hash, &public_key));
+        }
+    }
+
+    #[test]
+    fn sign_low_s() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let address = private_key.public_key().address();
+
+        for i in 0..16u8 {
+            let hash = keccak256(&[i]);
+            let signature = Signature::create(&private_key, hash);
+
+            assert!(signature.is_low_s());
+            assert_eq!(signature.ecrecover(&hash).unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn verify_strict_rejects_high_s() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let public_key = private_key.public_key();
+        let hash = keccak256("hello world".as_bytes());
+
+        let signature = Signature::create(&private_key, hash);
+        let malleable = Signature {
+            r: signature.r,
+            s: -signature.s,
+            v: 27 + ((signature.v - 27) ^ 1),
+        };
+
+        assert!(signature.verify_strict(&hash, &public_key));
+        assert!(malleable.verify(&hash, &public_key));
+        assert!(!malleable.verify_strict(&hash, &public_key));
+
+        // the flipped recovery id still recovers the same key
+        assert_eq!(malleable.ecrecover(&hash).unwrap(), public_key.address());
+        assert_eq!(malleable.normalize_s(), Some(signature));
+    }
+
+    #[test]
+    fn normalize_s_eip155() {
+        let signature = Signature {
+            r: Scalar::ONE,
+            s: -Scalar::ONE,
+            v: 37,
+        };
+        let normalized = signature.normalize_s().unwrap();
+
+        assert_eq!(normalized.s, Scalar::ONE);
+        assert_eq!(normalized.v, 38);
+        assert_eq!(normalized.chain_id(), Some(1));
+        assert_eq!(normalized.normalize_s(), Some(normalized));
+
+        // v = u64::MAX has an even recovery id, flipping it would need v + 1
+        let signature = Signature {
+            v: u64::MAX,
+            ..signature
+        };
+        assert_eq!(signature.normalize_s(), None);
+
+        let signature = Signature {
+            v: u64::MAX - 1,
+            ..signature
+        };
+        let normalized = signature.normalize_s().unwrap();
+        assert_eq!(normalized.v, u64::MAX - 2);
+        assert_eq!(normalized.chain_id(), signature.chain_id());
+    }
+
+    #[test]
+    fn normalize_s_without_recovery_id() {
+        // v set to a bare recovery id or to a value that doesn't encode one
+        for v in [0, 1, 31, 34] {
+            let signature = Signature {
+                r: Scalar::ONE,
+                s: -Scalar::ONE,
+                v,
+            };
+
+            assert_eq!(signature.normalize_s(), Some(signature));
+        }
+
+        let signature = Signature {
+            r: Scalar::ONE,
+            s: -Scalar::ONE,
+            v: 29,
+        };
+        let normalized = signature.normalize_s().unwrap();
+
+        assert_eq!(normalized.s, Scalar::ONE);
+        assert_eq!(normalized.v, 30);
+    }
+
+    #[test]
+    fn verify_rejects_zero_s() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let public_key = private_key.public_key();
+        let hash = keccak256("hello world".as_bytes());
+
+        let mut signature = Signature::create(&private_key, hash);
+        signature.s = Scalar::ZERO;
+
+        assert!(!signature.verify(&hash, &public_key));
+    }
+
+    #[test]
+    fn recover_errors() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let hash = keccak256("hello world".as_bytes());
+        let signature = Signature::create(&private_key, hash);
+
+        let recover = |r: Scalar, s: Scalar, v: u64| Signature { r, s, v }.ecrecover(&hash);
+
+        assert_eq!(
+            recover(signature.r, signature.s, 31),
+            Err(RecoveryError::InvalidRecoveryId(31))
+        );
+        assert_eq!(
+            recover(Scalar::ZERO, signature.s, 27),
+            Err(RecoveryError::ROutOfRange)
+        );
+        assert_eq!(
+            recover(signature.r, Scalar::ZERO, 27),
+            Err(RecoveryError::SOutOfRange)
+        );
+        // r + O is larger than P
+        assert_eq!(
+            recover(signature.r, signature.s, 29),
+            Err(RecoveryError::ROutOfRange)
+        );
+        // 5^3 + 7 is not a square mod P
+        assert_eq!(
+            recover(Scalar::from_u64(5), signature.s, 27),
+            Err(RecoveryError::NonResidue)
+        );
+    }
+
+    #[test]
+    fn recover_point_at_infinity() {
+        // with R = G, s = 1 and a hash of 1 the recovered key is (s * R - hash * G) / r = 0
+        let signature = Signature {
+            r: Scalar::from_bytes_be(&G.x.to_bytes_be()).unwrap(),
+            s: Scalar::ONE,
+            v: 27,
+        };
+
+        assert_eq!(
+            signature.recover_public_key(&Scalar::ONE.to_bytes_be()),
+            Err(RecoveryError::PointAtInfinity)
+        );
+        assert_eq!(
+            recover_public_keys_batch(&[(signature, Scalar::ONE.to_bytes_be())]),
+            [Err(RecoveryError::PointAtInfinity)]
+        );
+    }
+
     #[test]
     fn signature_from_bytes() {
         let signature1 = Signature {
             v: 27,
-            r: bigint_from_hex("1556a70d76cc452ae54e83bb167a9041f0d062d000fa0dcb42593f77c544f647")
+            r: Scalar::from_hex("1556a70d76cc452ae54e83bb167a9041f0d062d000fa0dcb42593f77c544f647")
                 .unwrap(),
-            s: bigint_from_hex("1643d14dbd6a6edc658f4b16699a585181a08dba4f6d16a9273e0e2cbed622da")
+            s: Scalar::from_hex("1643d14dbd6a6edc658f4b16699a585181a08dba4f6d16a9273e0e2cbed622da")
                 .unwrap(),
         };
 
-        let signature2 = Signature::from_signature_bytes(&signature1.to_signature_bytes())
+        let signature2 = Signature::from_signature_bytes(&signature1.to_signature_bytes().unwrap())
             .expect("failed to parse signature");
 
         let signature3 = Signature::from_signature_bytes(&hex::decode("1556a70d76cc452ae54e83bb167a9041f0d062d000fa0dcb42593f77c544f6471643d14dbd6a6edc658f4b16699a585181a08dba4f6d16a9273e0e2cbed622da1b").unwrap()).unwrap();
@@ -348,4 +853,198 @@ mod test {
         assert_eq!(signature1, signature2);
         assert_eq!(signature1, signature3);
     }
+
+    #[test]
+    fn signature_to_bytes_v() {
+        let signature = Signature {
+            r: Scalar::ONE,
+            s: Scalar::ONE,
+            v: 28,
+        };
+        let bytes = signature.to_signature_bytes().unwrap();
+        assert_eq!(bytes.len(), 65);
+        assert_eq!(bytes[64], 28);
+
+        // EIP-155 v values are written as 27 + recovery id instead of being truncated
+        for (v, expected) in [
+            (37, 27),
+            (38, 28),
+            (22310257, 27),
+            (22310258, 28),
+            (u64::MAX, 27),
+        ] {
+            let eip155 = Signature { v, ..signature };
+            assert_eq!(eip155.to_signature_bytes().unwrap()[64], expected);
+        }
+
+        for v in [0, 3, 26, 31, 34] {
+            let invalid = Signature { v, ..signature };
+            assert_eq!(invalid.to_signature_bytes(), None);
+        }
+    }
+
+    #[test]
+    fn signature_from_bytes_out_of_range() {
+        let order = hex::decode("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+            .unwrap();
+        let one = Scalar::ONE.to_bytes_be();
+
+        let high_r = [&order[..], &one[..], &[27u8][..]].concat();
+        let high_s = [&one[..], &order[..], &[27u8][..]].concat();
+
+        assert_eq!(Signature::from_signature_bytes(&high_r), None);
+        assert_eq!(Signature::from_signature_bytes(&high_s), None);
+        assert!(
+            Signature::from_signature_bytes(&[&one[..], &one[..], &[27u8][..]].concat()).is_some()
+        );
+    }
+
+    #[test]
+    fn signature_from_bytes_v() {
+        let one = Scalar::ONE.to_bytes_be();
+        let with_v = |v: u8| [&one[..], &one[..], &[v][..]].concat();
+
+        assert_eq!(Signature::from_signature_bytes(&with_v(0)).unwrap().v, 27);
+        assert_eq!(Signature::from_signature_bytes(&with_v(1)).unwrap().v, 28);
+        assert_eq!(Signature::from_signature_bytes(&with_v(28)).unwrap().v, 28);
+        assert_eq!(Signature::from_signature_bytes(&with_v(4)), None);
+        assert_eq!(Signature::from_signature_bytes(&with_v(31)), None);
+        assert_eq!(Signature::from_signature_bytes(&with_v(33)), None);
+        assert_eq!(Signature::from_signature_bytes(&one), None);
+
+        let zero = [0u8; 32];
+        let zero_r = [&zero[..], &one[..], &[27u8][..]].concat();
+        let zero_s = [&one[..], &zero[..], &[27u8][..]].concat();
+        assert_eq!(Signature::from_signature_bytes(&zero_r), None);
+        assert_eq!(Signature::from_signature_bytes(&zero_s), None);
+    }
+
+    #[test]
+    fn compact_eip2098_vectors() {
+        // https://eips.ethereum.org/EIPS/eip-2098#test-cases
+        let private_key = PrivateKey::from_hex(
+            "1234567890123456789012345678901234567890123456789012345678901234",
+        )
+        .unwrap();
+
+        let signature =
+            Signature::create(&private_key, crate::eip191::hash_message(b"Hello World"));
+        assert_eq!(signature.v, 27);
+        assert_eq!(
+            hex::encode(signature.to_compact_bytes().unwrap()),
+            "68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b90\
+             7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064"
+        );
+
+        let signature = Signature::create(
+            &private_key,
+            crate::eip191::hash_message(b"It's a small(er) world"),
+        );
+        assert_eq!(signature.v, 28);
+        let compact = signature.to_compact_bytes().unwrap();
+        assert_eq!(
+            hex::encode(compact),
+            "9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76\
+             939c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793"
+        );
+        assert_eq!(Signature::from_compact_bytes(&compact), Some(signature));
+    }
+
+    #[test]
+    fn compact_rejects_invalid() {
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let signature = Signature::create(&private_key, keccak256("hello world".as_bytes()));
+
+        let high_s = Signature {
+            s: -signature.s,
+            ..signature
+        };
+        assert_eq!(high_s.to_compact_bytes(), None);
+
+        let high_recovery_id = Signature { v: 29, ..signature };
+        assert_eq!(high_recovery_id.to_compact_bytes(), None);
+
+        // the EIP-155 chain id is dropped
+        let eip155 =
+            Signature::create_with_chain_id(&private_key, keccak256(b"eip155"), 1).unwrap();
+        let compact = eip155.to_compact_bytes().unwrap();
+        assert_eq!(
+            Signature::from_compact_bytes(&compact)
+                .unwrap()
+                .recovery_id(),
+            eip155.recovery_id()
+        );
+
+        let compact = signature.to_compact_bytes().unwrap();
+        assert_eq!(Signature::from_compact_bytes(&compact[..63]), None);
+
+        assert_eq!(Signature::from_compact_bytes(&[0u8; 64]), None);
+    }
+
+    #[test]
+    fn der_encoding() {
+        let signature = Signature {
+            r: Scalar::ONE,
+            s: Scalar::from_u64(0x7f),
+            v: 27,
+        };
+        assert_eq!(hex::encode(signature.to_der()), "300602010102017f");
+        assert_eq!(
+            Signature::from_der(&signature.to_der(), 0),
+            Some(signature.clone())
+        );
+
+        // the top bit of s is set so it gets a zero byte in front
+        let signature = Signature {
+            s: Scalar::from_u64(0x80),
+            ..signature
+        };
+        assert_eq!(hex::encode(signature.to_der()), "300702010102020080");
+        assert_eq!(Signature::from_der(&signature.to_der(), 0), Some(signature));
+
+        let private_key = PrivateKey::from_hex(
+            "c7fb672c8a1ae5a87fbd97bba7aa5a9024dc9dc7a3cfa97b3759af744008195a",
+        )
+        .unwrap();
+        let hash = keccak256("hello world".as_bytes());
+        let signature = Signature::create(&private_key, hash);
+        let parsed = Signature::from_der(&signature.to_der(), signature.v as u8 - 27).unwrap();
+
+        assert_eq!(parsed, signature);
+        assert!(parsed.verify(&hash, &private_key.public_key()));
+    }
+
+    #[test]
+    fn der_rejects_non_strict() {
+        let from_der = |hex: &str| Signature::from_der(&hex::decode(hex).unwrap(), 0);
+
+        assert!(from_der("300602010102017f").is_some());
+        // negative s
+        assert_eq!(from_der("3006020101020181"), None);
+        // unnecessary zero padding
+        assert_eq!(from_der("30070201010202007f"), None);
+        // zero length integer
+        assert_eq!(from_der("30050201010200"), None);
+        // sequence length doesn't match
+        assert_eq!(from_der("300702010102017f"), None);
+        // trailing bytes after s
+        assert_eq!(from_der("300702010102017f00"), None);
+        // wrong tags
+        assert_eq!(from_der("310602010102017f"), None);
+        assert_eq!(from_der("300603010102017f"), None);
+        // zero r
+        assert_eq!(from_der("300602010002017f"), None);
+        // 33 byte r without a sign byte
+        assert_eq!(
+            from_der(&format!("3026022101{}02017f", "00".repeat(32))),
+            None
+        );
+        assert_eq!(
+            Signature::from_der(&hex::decode("300602010102017f").unwrap(), 4),
+            None
+        );
+    }
 }
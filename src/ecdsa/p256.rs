@@ -0,0 +1,420 @@
+//! ECDSA verification on secp256r1 (P-256), the curve used by passkeys and secure enclaves.
+//! https://github.com/ethereum/RIPs/blob/master/RIPS/rip-7212.md
+//!
+//! P-256 is y^2 = x^3 - 3x + b. It has no efficient endomorphism, so multiplications use
+//! plain wNAF, but otherwise it shares all of the curve arithmetic with secp256k1. Only
+//! verification is supported, which is what the P256VERIFY precompile needs.
+
+use once_cell::sync::Lazy;
+
+use super::{
+    curve::{AffinePoint, Curve, CurveField, CurveScalar, GeneratorTable},
+    field::PrimeField,
+    PublicKeyError,
+};
+
+/// the P-256 base field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P256Field;
+
+impl PrimeField for P256Field {
+    const MODULUS: [u64; 4] = [
+        0xffffffffffffffff,
+        0x00000000ffffffff,
+        0x0000000000000000,
+        0xffffffff00000001,
+    ];
+    const R: [u64; 4] = [
+        0x0000000000000001,
+        0xffffffff00000000,
+        0xffffffffffffffff,
+        0x00000000fffffffe,
+    ];
+    const R2: [u64; 4] = [
+        0x0000000000000003,
+        0xfffffffbffffffff,
+        0xfffffffffffffffe,
+        0x00000004fffffffd,
+    ];
+    const INV: u64 = 0x0000000000000001;
+}
+
+/// the P-256 scalar field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P256Scalar;
+
+impl PrimeField for P256Scalar {
+    const MODULUS: [u64; 4] = [
+        0xf3b9cac2fc632551,
+        0xbce6faada7179e84,
+        0xffffffffffffffff,
+        0xffffffff00000000,
+    ];
+    const R: [u64; 4] = [
+        0x0c46353d039cdaaf,
+        0x4319055258e8617b,
+        0x0000000000000000,
+        0x00000000ffffffff,
+    ];
+    const R2: [u64; 4] = [
+        0x83244c95be79eea2,
+        0x4699799c49bd6fa6,
+        0x2845b2392b6bec59,
+        0x66e12d94f3d95620,
+    ];
+    const INV: u64 = 0xccd1c8aaee00bc4f;
+}
+
+/// secp256r1, also known as P-256 or prime256v1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P256;
+
+impl Curve for P256 {
+    type Field = P256Field;
+    type Scalar = P256Scalar;
+
+    fn a() -> CurveField<Self> {
+        *A
+    }
+
+    fn b() -> CurveField<Self> {
+        *B
+    }
+
+    fn generator() -> AffinePoint<Self> {
+        *G
+    }
+
+    fn generator_table() -> &'static GeneratorTable<Self> {
+        &G_TABLE
+    }
+}
+
+static A: Lazy<CurveField<P256>> = Lazy::new(|| -CurveField::<P256>::from_u64(3));
+
+static B: Lazy<CurveField<P256>> = Lazy::new(|| {
+    CurveField::<P256>::from_hex("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b")
+        .unwrap()
+});
+
+static G: Lazy<AffinePoint<P256>> = Lazy::new(|| {
+    AffinePoint::from_hex(
+        "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+        "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+    )
+});
+
+static G_TABLE: Lazy<GeneratorTable<P256>> = Lazy::new(GeneratorTable::new);
+
+/// a P-256 public key, always a point on the curve other than infinity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P256PublicKey(AffinePoint<P256>);
+
+impl P256PublicKey {
+    pub fn from_point(point: AffinePoint<P256>) -> Result<Self, PublicKeyError> {
+        if point.is_infinity() {
+            return Err(PublicKeyError::Infinity);
+        }
+
+        if !point.is_on_curve() {
+            return Err(PublicKeyError::NotOnCurve);
+        }
+
+        Ok(Self(point))
+    }
+
+    /// the affine coordinates as 32 byte big endian numbers, e.g. from a WebAuthn COSE key
+    pub fn from_coordinates(x: &[u8; 32], y: &[u8; 32]) -> Result<Self, PublicKeyError> {
+        let x = CurveField::<P256>::from_bytes_be(x).ok_or(PublicKeyError::CoordinateOutOfRange)?;
+        let y = CurveField::<P256>::from_bytes_be(y).ok_or(PublicKeyError::CoordinateOutOfRange)?;
+
+        Self::from_point(AffinePoint::new(x, y))
+    }
+
+    pub fn point(&self) -> &AffinePoint<P256> {
+        &self.0
+    }
+}
+
+/// a P-256 ECDSA signature, without a recovery id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P256Signature {
+    pub r: CurveScalar<P256>,
+    pub s: CurveScalar<P256>,
+}
+
+impl P256Signature {
+    /// parses r || s, returns `None` if the length is wrong or r or s are not smaller than N
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 64 {
+            return None;
+        }
+
+        let r = CurveScalar::<P256>::from_bytes_be(bytes[0..32].try_into().unwrap())?;
+        let s = CurveScalar::<P256>::from_bytes_be(bytes[32..64].try_into().unwrap())?;
+
+        Some(Self { r, s })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r.to_bytes_be());
+        bytes[32..].copy_from_slice(&self.s.to_bytes_be());
+        bytes
+    }
+
+    /// verifies the signature of a 32 byte hash, accepting any s in [1, N)
+    /// like RIP-7212 this doesn't reject malleable high s signatures
+    pub fn verify(&self, hash: &[u8; 32], public_key: &P256PublicKey) -> bool {
+        if self.r.is_zero() || self.s.is_zero() {
+            return false;
+        }
+
+        // N is larger than 2^255, so reducing the hash is the same as truncating it
+        let hash = CurveScalar::<P256>::from_bytes_be_reduced(hash);
+
+        let w = self.s.invert();
+        let u1 = hash * w;
+        let u2 = self.r * w;
+
+        let r_point = G_TABLE.mul_add(&u1, public_key.point(), &u2).to_affine();
+
+        if r_point.is_infinity() {
+            return false;
+        }
+
+        CurveScalar::<P256>::from_bytes_be_reduced(&r_point.x.to_bytes_be()) == self.r
+    }
+}
+
+/// the P256VERIFY precompile from RIP-7212
+///
+/// the input is hash || r || s || x || y, 160 bytes in total, the output is 1 as a 32 byte word
+/// if the signature is valid and empty otherwise (including for malformed input)
+pub fn p256_verify(input: &[u8]) -> Vec<u8> {
+    if input.len() != 160 {
+        return Vec::new();
+    }
+
+    let hash: &[u8; 32] = input[0..32].try_into().unwrap();
+    let signature = match P256Signature::from_bytes(&input[32..96]) {
+        Some(signature) => signature,
+        None => return Vec::new(),
+    };
+    let public_key = match P256PublicKey::from_coordinates(
+        input[96..128].try_into().unwrap(),
+        input[128..160].try_into().unwrap(),
+    ) {
+        Ok(public_key) => public_key,
+        Err(_) => return Vec::new(),
+    };
+
+    if signature.verify(hash, &public_key) {
+        let mut output = vec![0u8; 32];
+        output[31] = 1;
+        output
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scalar(n: u64) -> CurveScalar<P256> {
+        CurveScalar::<P256>::from_u64(n)
+    }
+
+    fn bytes32(hex: &str) -> [u8; 32] {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    /// hash, r, s, x, y
+    const VECTORS: [[&str; 5]; 3] = [
+        // RFC 6979 A.2.5, SHA-256 of "sample" and "test"
+        [
+            "af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf",
+            "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+            "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+            "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6",
+            "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299",
+        ],
+        [
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+            "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+            "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+            "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6",
+            "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299",
+        ],
+        // signed by the Python cryptography package, SHA-256 of "passkey"
+        [
+            "4b949c130904506119a31ad2ca94bc9a97f56be914676fe08a5de594ea4c96bd",
+            "8cb4b0160101d194f57ac064bab1a375dc592dcc4740b405b42159e1b925d2f6",
+            "7b568fedab5397a83d5920e0ab0637c4a36ad5d9f1501eeddb2ac1379768edc5",
+            "42a233784adc525236947453174017847074425a4dc7d49c884522a8673aa068",
+            "ce94c989e8e87261ee5719949c1cd749720f81055d5589644c211db91f5d7b39",
+        ],
+    ];
+
+    fn precompile_input(vector: &[&str; 5]) -> Vec<u8> {
+        hex::decode(vector.concat()).unwrap()
+    }
+
+    #[test]
+    fn curve_arithmetic() {
+        assert!(G.is_on_curve());
+        assert!(!AffinePoint::<P256>::new(G.x, G.y + CurveField::<P256>::ONE).is_on_curve());
+
+        let double_g = AffinePoint::<P256>::from_hex(
+            "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+            "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+        );
+        assert_eq!(G.add(&G), double_g);
+        assert_eq!(G.to_jacobian().double().to_affine(), double_g);
+        assert_eq!(G.mul(&scalar(2)), double_g);
+
+        assert!(G.mul(&-scalar(1)).add(&G).is_infinity());
+        assert_eq!(G.mul(&-scalar(5)), G.mul(&scalar(5)).inverse());
+    }
+
+    #[test]
+    fn mul_paths_agree() {
+        let point = G.mul(&scalar(1337));
+        let scalars = [
+            scalar(0),
+            scalar(1),
+            scalar(255),
+            -scalar(1),
+            CurveScalar::<P256>::from_hex(
+                "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+            )
+            .unwrap(),
+        ];
+
+        for k in scalars.iter() {
+            assert_eq!(G.mul(k), G_TABLE.mul(k), "scalar {}", k);
+            assert_eq!(G.mul(k), G.mul_secret(k), "scalar {}", k);
+            assert_eq!(point.mul(k), point.mul_secret(k), "scalar {}", k);
+            assert_eq!(
+                G_TABLE.mul_add(k, &point, &scalar(3)).to_affine(),
+                G.mul(k).add(&point.mul(&scalar(3)))
+            );
+        }
+    }
+
+    #[test]
+    fn public_key_from_private_key() {
+        // the RFC 6979 key
+        let private_key = CurveScalar::<P256>::from_hex(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        )
+        .unwrap();
+        let public_key =
+            P256PublicKey::from_coordinates(&bytes32(VECTORS[0][3]), &bytes32(VECTORS[0][4]))
+                .unwrap();
+
+        assert_eq!(G.mul_secret(&private_key), *public_key.point());
+    }
+
+    #[test]
+    fn from_x() {
+        let point = AffinePoint::<P256>::from_x(G.x, G.y.is_odd()).unwrap();
+        assert_eq!(point, *G);
+        assert_eq!(
+            AffinePoint::<P256>::from_x(G.x, !G.y.is_odd()).unwrap(),
+            G.inverse()
+        );
+
+        // 1 - 3 + b is not a square mod P
+        assert_eq!(
+            AffinePoint::<P256>::from_x(CurveField::<P256>::ONE, false),
+            None
+        );
+    }
+
+    #[test]
+    fn verify_vectors() {
+        for vector in VECTORS.iter() {
+            let hash = bytes32(vector[0]);
+            let signature =
+                P256Signature::from_bytes(&hex::decode(vector[1..3].concat()).unwrap()).unwrap();
+            let public_key =
+                P256PublicKey::from_coordinates(&bytes32(vector[3]), &bytes32(vector[4])).unwrap();
+
+            assert!(signature.verify(&hash, &public_key));
+            assert_eq!(hex::encode(signature.to_bytes()), vector[1..3].concat());
+
+            // the malleable signature is valid too
+            let high_s = P256Signature {
+                r: signature.r,
+                s: -signature.s,
+            };
+            assert!(high_s.verify(&hash, &public_key));
+
+            let mut wrong_hash = hash;
+            wrong_hash[31] ^= 1;
+            assert!(!signature.verify(&wrong_hash, &public_key));
+
+            let wrong_r = P256Signature {
+                r: signature.r + CurveScalar::<P256>::ONE,
+                s: signature.s,
+            };
+            assert!(!wrong_r.verify(&hash, &public_key));
+        }
+
+        // the RFC 6979 signature of "sample" doesn't match the hash of "test"
+        let signature =
+            P256Signature::from_bytes(&hex::decode(VECTORS[0][1..3].concat()).unwrap()).unwrap();
+        let public_key =
+            P256PublicKey::from_coordinates(&bytes32(VECTORS[0][3]), &bytes32(VECTORS[0][4]))
+                .unwrap();
+        assert!(!signature.verify(&bytes32(VECTORS[1][0]), &public_key));
+    }
+
+    #[test]
+    fn precompile() {
+        let mut valid = vec![0u8; 32];
+        valid[31] = 1;
+
+        for vector in VECTORS.iter() {
+            assert_eq!(p256_verify(&precompile_input(vector)), valid);
+        }
+
+        let input = precompile_input(&VECTORS[0]);
+        assert_eq!(p256_verify(&input[..159]), Vec::<u8>::new());
+        assert_eq!(
+            p256_verify(&[input.as_slice(), &[0]].concat()),
+            Vec::<u8>::new()
+        );
+        assert_eq!(p256_verify(&[]), Vec::<u8>::new());
+
+        let n = P256Scalar::MODULUS;
+        let mut n_bytes = [0u8; 32];
+        for (i, limb) in n.iter().enumerate() {
+            n_bytes[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+        }
+
+        // r and s have to be in [1, N)
+        let mut modified = input.clone();
+        modified[32..64].copy_from_slice(&[0; 32]);
+        assert_eq!(p256_verify(&modified), Vec::<u8>::new());
+
+        let mut modified = input.clone();
+        modified[64..96].copy_from_slice(&n_bytes);
+        assert_eq!(p256_verify(&modified), Vec::<u8>::new());
+
+        // the public key has to be on the curve, (0, 0) isn't
+        let mut modified = input.clone();
+        modified[159] ^= 1;
+        assert_eq!(p256_verify(&modified), Vec::<u8>::new());
+
+        let mut modified = input.clone();
+        modified[96..160].copy_from_slice(&[0; 64]);
+        assert_eq!(p256_verify(&modified), Vec::<u8>::new());
+
+        let mut modified = input;
+        modified[96..128].copy_from_slice(&[0xff; 32]);
+        assert_eq!(p256_verify(&modified), Vec::<u8>::new());
+    }
+}
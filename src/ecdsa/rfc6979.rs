@@ -0,0 +1,167 @@
+//! Deterministic nonce generation for ECDSA as described in RFC 6979.
+//! https://www.rfc-editor.org/rfc/rfc6979#section-3.2
+//!
+//! The nonce `k` is derived from the private key and the message hash with
+//! HMAC-SHA256, so signing the same hash with the same key always gives the
+//! same signature and we don't depend on the quality of a system RNG.
+//! This is the same construction used by libsecp256k1 and most Ethereum signers.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use super::field::Scalar;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// a stream of candidate nonces for a single (private key, hash) pair
+///
+/// the first value is the RFC 6979 nonce, further values are only needed
+/// in the (astronomically unlikely) case that a nonce produces r = 0 or s = 0
+pub struct NonceGenerator {
+    k: [u8; 32],
+    v: [u8; 32],
+    first: bool,
+}
+
+impl NonceGenerator {
+    /// `extra_entropy` is the optional additional data k' from section 3.6,
+    /// appended after the hash when seeding the HMAC_DRBG
+    pub fn new(private_key: &[u8; 32], hash: &[u8; 32], extra_entropy: Option<&[u8; 32]>) -> Self {
+        let x = private_key;
+        let h1 = bits_to_octets(hash);
+        let extra: &[u8] = match extra_entropy {
+            Some(extra) => extra,
+            None => &[],
+        };
+
+        let mut k = [0u8; 32];
+        let mut v = [1u8; 32];
+
+        k = hmac(&k, &[&v, &[0x00], x, &h1, extra]);
+        v = hmac(&k, &[&v]);
+        k = hmac(&k, &[&v, &[0x01], x, &h1, extra]);
+        v = hmac(&k, &[&v]);
+
+        Self { k, v, first: true }
+    }
+
+    /// returns the next candidate k in the range [1, O)
+    pub fn next_nonce(&mut self) -> Scalar {
+        loop {
+            if !self.first {
+                self.k = hmac(&self.k, &[&self.v, &[0x00]]);
+                self.v = hmac(&self.k, &[&self.v]);
+            }
+            self.first = false;
+
+            self.v = hmac(&self.k, &[&self.v]);
+
+            if let Some(candidate) = Scalar::from_bytes_be(&self.v) {
+                if !candidate.is_zero() {
+                    return candidate;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for NonceGenerator {
+    fn drop(&mut self) {
+        // the state is derived from the private key
+        self.k.zeroize();
+        self.v.zeroize();
+    }
+}
+
+fn hmac(key: &[u8; 32], data: &[&[u8]]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in data {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// the hash reduced mod O, since the hash and the curve order are both 256 bits
+/// long no truncation is needed
+fn bits_to_octets(hash: &[u8; 32]) -> [u8; 32] {
+    Scalar::from_bytes_be_reduced(hash).to_bytes_be()
+}
+
+#[cfg(test)]
+mod test {
+    use sha2::Digest;
+
+    use super::*;
+
+    // test vectors for secp256k1 with SHA256 message hashes,
+    // as used by python-ecdsa, trezor-crypto and bitcoinjs
+
+    fn nonce(private_key: &Scalar, message: &str) -> Scalar {
+        let hash: [u8; 32] = Sha256::digest(message.as_bytes()).into();
+        NonceGenerator::new(&private_key.to_bytes_be(), &hash, None).next_nonce()
+    }
+
+    #[test]
+    fn nonce_satoshi() {
+        assert_eq!(
+            nonce(&Scalar::ONE, "Satoshi Nakamoto"),
+            Scalar::from_hex("8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn nonce_tears_in_rain() {
+        assert_eq!(
+            nonce(
+                &Scalar::ONE,
+                "All those moments will be lost in time, like tears in rain. Time to die..."
+            ),
+            Scalar::from_hex("38aa22d72376b4dbc472e06c3ba403ee0a394da63fc58d88686c611aba98d6b3")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn nonce_max_key() {
+        assert_eq!(
+            nonce(&-Scalar::ONE, "Satoshi Nakamoto"),
+            Scalar::from_hex("33a19b60e25fb6f4435af53a3d42d493644827367e6453928554f43e49aa6f90")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn nonce_alan_turing() {
+        let private_key =
+            Scalar::from_hex("f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181")
+                .unwrap();
+        assert_eq!(
+            nonce(&private_key, "Alan Turing"),
+            Scalar::from_hex("525a82b70e67874398067543fd84c83d30c175fdc45fdeee082fe13b1d7cfdf1")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn extra_entropy_changes_nonce() {
+        let hash: [u8; 32] = Sha256::digest(b"Satoshi Nakamoto").into();
+        let key = Scalar::ONE.to_bytes_be();
+        let plain = NonceGenerator::new(&key, &hash, None).next_nonce();
+        let extra = NonceGenerator::new(&key, &hash, Some(&[7u8; 32])).next_nonce();
+        let extra2 = NonceGenerator::new(&key, &hash, Some(&[7u8; 32])).next_nonce();
+
+        assert_ne!(plain, extra);
+        assert_eq!(extra, extra2);
+    }
+
+    #[test]
+    fn nonces_are_distinct() {
+        let hash = [1u8; 32];
+        let mut generator = NonceGenerator::new(&Scalar::ONE.to_bytes_be(), &hash, None);
+        let k1 = generator.next_nonce();
+        let k2 = generator.next_nonce();
+        assert_ne!(k1, k2);
+    }
+}
@@ -0,0 +1,322 @@
+//! ECIES as used by devp2p for the RLPx handshake, compatible with go-ethereum.
+//! https://github.com/ethereum/devp2p/blob/master/rlpx.md#ecies-encryption
+//!
+//! A message m for the public key K is encrypted as `R || iv || c || d`, where
+//! R = r * G is a fresh ephemeral key (65 bytes, uncompressed) and the x coordinate of
+//! r * K is turned into keys with the NIST SP 800-56 concat KDF: kE || kM = KDF(S, 32).
+//! c = AES-128-CTR(kE, iv, m) and d = HMAC-SHA256(sha256(kM), iv || c || s2), where s2
+//! is optional data that's authenticated but not encrypted (RLPx uses the message size).
+
+use std::fmt::Display;
+
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::ecdsa::{Point, PrivateKey, PublicKey, PublicKeyError, SharedSecret};
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// length of the ephemeral public key at the start of every ciphertext
+const PUBLIC_KEY_LENGTH: usize = 65;
+const IV_LENGTH: usize = 16;
+const TAG_LENGTH: usize = 32;
+
+/// how much longer the ciphertext is than the message
+pub const OVERHEAD: usize = PUBLIC_KEY_LENGTH + IV_LENGTH + TAG_LENGTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesError {
+    /// the recipient key or the ephemeral key in the ciphertext is not a valid point
+    InvalidPublicKey(PublicKeyError),
+    /// the ciphertext is shorter than `OVERHEAD`
+    TooShort(usize),
+    /// the HMAC tag doesn't match, the ciphertext was modified or isn't for this key
+    InvalidMac,
+}
+
+impl Display for EciesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EciesError::InvalidPublicKey(error) => write!(f, "invalid public key: {}", error),
+            EciesError::TooShort(len) => write!(f, "ciphertext is too short ({} bytes)", len),
+            EciesError::InvalidMac => write!(f, "invalid message authentication code"),
+        }
+    }
+}
+
+impl std::error::Error for EciesError {}
+
+impl From<PublicKeyError> for EciesError {
+    fn from(error: PublicKeyError) -> Self {
+        EciesError::InvalidPublicKey(error)
+    }
+}
+
+/// encrypts a message for the owner of `recipient`, with a random ephemeral key and iv
+pub fn encrypt(
+    recipient: &Point,
+    message: &[u8],
+    shared_mac_data: &[u8],
+) -> Result<Vec<u8>, EciesError> {
+    let recipient = PublicKey::from_point(*recipient)?;
+    let ephemeral_key = PrivateKey::random();
+
+    let mut iv = [0u8; IV_LENGTH];
+    OsRng.fill_bytes(&mut iv);
+
+    Ok(encrypt_with(
+        &recipient,
+        &ephemeral_key,
+        &iv,
+        message,
+        shared_mac_data,
+    ))
+}
+
+/// decrypts a message encrypted for our key, after checking its HMAC tag
+pub fn decrypt(
+    private_key: &PrivateKey,
+    ciphertext: &[u8],
+    shared_mac_data: &[u8],
+) -> Result<Vec<u8>, EciesError> {
+    if ciphertext.len() < OVERHEAD {
+        return Err(EciesError::TooShort(ciphertext.len()));
+    }
+
+    let (ephemeral_key, rest) = ciphertext.split_at(PUBLIC_KEY_LENGTH);
+    let (iv_and_message, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+    let ephemeral_key = PublicKey::from_bytes(ephemeral_key)?;
+    let keys = Keys::derive(&private_key.diffie_hellman(&ephemeral_key));
+
+    let mut mac = HmacSha256::new_from_slice(&keys.mac).expect("HMAC accepts keys of any length");
+    mac.update(iv_and_message);
+    mac.update(shared_mac_data);
+    // constant time comparison
+    mac.verify_slice(tag).map_err(|_| EciesError::InvalidMac)?;
+
+    let (iv, encrypted) = iv_and_message.split_at(IV_LENGTH);
+    let mut message = encrypted.to_vec();
+    Aes128Ctr::new(&keys.encryption.into(), iv.into()).apply_keystream(&mut message);
+
+    Ok(message)
+}
+
+fn encrypt_with(
+    recipient: &PublicKey,
+    ephemeral_key: &PrivateKey,
+    iv: &[u8; IV_LENGTH],
+    message: &[u8],
+    shared_mac_data: &[u8],
+) -> Vec<u8> {
+    let keys = Keys::derive(&ephemeral_key.diffie_hellman(recipient));
+
+    let mut result = Vec::with_capacity(message.len() + OVERHEAD);
+    result.extend_from_slice(&ephemeral_key.public_key().to_bytes_uncompressed());
+    result.extend_from_slice(iv);
+
+    let start = result.len();
+    result.extend_from_slice(message);
+    Aes128Ctr::new(&keys.encryption.into(), iv.into()).apply_keystream(&mut result[start..]);
+
+    let mut mac = HmacSha256::new_from_slice(&keys.mac).expect("HMAC accepts keys of any length");
+    mac.update(&result[PUBLIC_KEY_LENGTH..]);
+    mac.update(shared_mac_data);
+    result.extend_from_slice(&mac.finalize().into_bytes());
+
+    result
+}
+
+/// the AES and HMAC keys derived from a shared secret
+struct Keys {
+    encryption: [u8; 16],
+    mac: [u8; 32],
+}
+
+impl Keys {
+    fn derive(shared_secret: &SharedSecret) -> Self {
+        let mut key_material = concat_kdf(&shared_secret.to_bytes(), 32);
+
+        let mut encryption = [0u8; 16];
+        encryption.copy_from_slice(&key_material[..16]);
+        // geth hashes the second half before using it as the HMAC key
+        let mac = Sha256::digest(&key_material[16..]).into();
+
+        key_material.zeroize();
+        Self { encryption, mac }
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.encryption.zeroize();
+        self.mac.zeroize();
+    }
+}
+
+/// NIST SP 800-56 concatenation KDF with SHA256 and no other info,
+/// the hashes of counter || secret for counter = 1, 2, ... until there are `length` bytes
+fn concat_kdf(secret: &[u8], length: usize) -> Vec<u8> {
+    let mut result = Vec::with_capacity(length + 32);
+    let mut counter = 1u32;
+
+    while result.len() < length {
+        let block = Sha256::new()
+            .chain_update(counter.to_be_bytes())
+            .chain_update(secret)
+            .finalize();
+        result.extend_from_slice(&block);
+        counter += 1;
+    }
+
+    result.truncate(length);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn recipient() -> PrivateKey {
+        PrivateKey::from_hex("b4d39783863980d393ef99e0b68711a407b4cdb92cab6a27899af9a178a01c93")
+            .unwrap()
+    }
+
+    #[test]
+    fn encrypt_vector() {
+        // the same implementation as in `decrypt_external_vector` gives this ciphertext too
+        let ephemeral_key = PrivateKey::from_hex(
+            "7b2f17cf50ef33bcb8b404d718b2e1fde3f2d025fe34f8d3f4c6e526e447ef13",
+        )
+        .unwrap();
+        let iv: [u8; 16] = core::array::from_fn(|i| i as u8);
+
+        let ciphertext = encrypt_with(
+            &recipient().public_key(),
+            &ephemeral_key,
+            &iv,
+            b"Hello, devp2p!",
+            &[1, 2],
+        );
+
+        assert_eq!(
+            hex::encode(&ciphertext),
+            "0414397848a6600eee675f59fb7829917a30c6dca7f1e7c82bdffbb7774978fe9869a4b3d6eb4b07effe83cc1bb59b6235db3026ac4d67e6183dd99a4335ef37b9000102030405060708090a0b0c0d0e0f7a518fd151af7c6a9ae0ae6ae12b3c970085d3b81c9bd7fdc790c3194f973442c9b98eb221616db5b0c8a54b4143"
+        );
+        assert_eq!(
+            decrypt(&recipient(), &ciphertext, &[1, 2]),
+            Ok(b"Hello, devp2p!".to_vec())
+        );
+    }
+
+    #[test]
+    fn decrypt_external_vector() {
+        // encrypted by a separate implementation of go-ethereum's crypto/ecies on top of
+        // OpenSSL (ECDH, ConcatKDFHash, AES-128-CTR and HMAC-SHA256 from Python's cryptography)
+        // with "Key B" of the EIP-8 test vectors as the recipient and 0x00f1 as s2,
+        // which is how RLPx authenticates the size prefix of the auth message
+        let recipient = PrivateKey::from_hex(
+            "b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291",
+        )
+        .unwrap();
+        let ciphertext = hex::decode(
+            "04654d1044b69c577a44e5f01a1209523adb4026e70c62d1c13a067acabc09d2667a49821a0ad4b634554d330a15a58fe61f8a8e0544b310c6de7b0c8da7528a8d7c23fe3a4a6dbd4c5f2c3c0bd35f0b8ec6cbf7715111f55f8623d645382bc6e863079dad4389bbac11b217b71c6f75e861d110c04902e5bc096b1df5c204d49e1b87a8592bfa8f336f4ab2cccb808d2cde59",
+        )
+        .unwrap();
+
+        assert_eq!(
+            decrypt(&recipient, &ciphertext, &[0x00, 0xf1]),
+            Ok(b"an RLPx auth message would go here".to_vec())
+        );
+        assert_eq!(
+            decrypt(&recipient, &ciphertext, &[0x00, 0xf2]),
+            Err(EciesError::InvalidMac)
+        );
+    }
+
+    #[test]
+    fn encrypt_decrypt_roundtrip() {
+        let recipient = recipient();
+        let point = *recipient.public_key().point();
+
+        for message in [&b""[..], b"x", &[7u8; 1000]] {
+            let ciphertext = encrypt(&point, message, b"").unwrap();
+
+            assert_eq!(ciphertext.len(), message.len() + OVERHEAD);
+            assert_eq!(decrypt(&recipient, &ciphertext, b""), Ok(message.to_vec()));
+        }
+
+        // the ephemeral key and iv are random
+        assert_ne!(
+            encrypt(&point, b"x", b"").unwrap(),
+            encrypt(&point, b"x", b"").unwrap()
+        );
+    }
+
+    #[test]
+    fn decrypt_rejects_modified_ciphertexts() {
+        let recipient = recipient();
+        let point = *recipient.public_key().point();
+        let ciphertext = encrypt(&point, b"attack at dawn", b"size").unwrap();
+
+        // every byte after the ephemeral key is covered by the tag
+        for i in PUBLIC_KEY_LENGTH..ciphertext.len() {
+            let mut modified = ciphertext.clone();
+            modified[i] ^= 1;
+            assert_eq!(
+                decrypt(&recipient, &modified, b"size"),
+                Err(EciesError::InvalidMac)
+            );
+        }
+
+        assert_eq!(
+            decrypt(&recipient, &ciphertext, b"other"),
+            Err(EciesError::InvalidMac)
+        );
+        assert_eq!(
+            decrypt(&PrivateKey::random(), &ciphertext, b"size"),
+            Err(EciesError::InvalidMac)
+        );
+
+        let mut modified = ciphertext.clone();
+        modified[64] ^= 1;
+        assert_eq!(
+            decrypt(&recipient, &modified, b"size"),
+            Err(EciesError::InvalidPublicKey(PublicKeyError::NotOnCurve))
+        );
+
+        assert_eq!(
+            decrypt(&recipient, &ciphertext[..OVERHEAD - 1], b"size"),
+            Err(EciesError::TooShort(OVERHEAD - 1))
+        );
+    }
+
+    #[test]
+    fn encrypt_rejects_invalid_points() {
+        assert_eq!(
+            encrypt(&Point::infinity(), b"x", b""),
+            Err(EciesError::InvalidPublicKey(PublicKeyError::Infinity))
+        );
+    }
+
+    #[test]
+    fn kdf_blocks() {
+        let secret = [0x42u8; 32];
+        let long = concat_kdf(&secret, 48);
+
+        assert_eq!(
+            long[..32],
+            Sha256::digest([&[0, 0, 0, 1][..], &secret].concat())[..]
+        );
+        assert_eq!(
+            long[32..],
+            Sha256::digest([&[0, 0, 0, 2][..], &secret].concat())[..16]
+        );
+        assert_eq!(concat_kdf(&secret, 32), long[..32]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Signed data as described in EIP-191, used for signing messages with an account key.
+//! https://eips.ethereum.org/EIPS/eip-191
+//!
+//! Signed data starts with 0x19, so it can never be a valid RLP encoded transaction,
+//! followed by a version byte and data that depends on the version:
+//! - 0x00: `0x19 || 0x00 || validator address || data`, data for a specific validator contract
+//! - 0x01: `0x19 || 0x01 || domain separator || hashStruct(message)`, EIP-712 typed data
+//! - 0x45 ("E"): `"\x19Ethereum Signed Message:\n" || len(message) || message`, used by
+//!   `personal_sign` and `eth_sign`
+
+use std::fmt::Display;
+
+use crate::{
+    address::Address,
+    ecdsa::{PrivateKey, RecoveryError, Signature},
+    keccak::keccak256,
+};
+
+const PERSONAL_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+
+/// data to be signed, for one of the EIP-191 versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedData<'a> {
+    /// version 0x00, data meant for the contract at `validator`
+    WithValidator {
+        validator: &'a Address,
+        data: &'a [u8],
+    },
+    /// version 0x01, EIP-712 typed data that's already been hashed
+    /// the message hash is left out when the primary type is the domain itself
+    Structured {
+        domain_separator: [u8; 32],
+        message_hash: Option<[u8; 32]>,
+    },
+    /// version 0x45, a `personal_sign` message
+    PersonalMessage(&'a [u8]),
+}
+
+impl SignedData<'_> {
+    pub fn version(&self) -> u8 {
+        match self {
+            SignedData::WithValidator { .. } => 0x00,
+            SignedData::Structured { .. } => 0x01,
+            SignedData::PersonalMessage(_) => 0x45,
+        }
+    }
+
+    /// the bytes that are hashed and signed, starting with 0x19 and the version byte
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            SignedData::WithValidator { validator, data } => {
+                let mut result = Vec::with_capacity(2 + 20 + data.len());
+                result.extend_from_slice(&[0x19, self.version()]);
+                result.extend_from_slice(&validator.to_bytes());
+                result.extend_from_slice(data);
+                result
+            }
+            SignedData::Structured {
+                domain_separator,
+                message_hash,
+            } => {
+                let mut result = Vec::with_capacity(2 + 32 + 32);
+                result.extend_from_slice(&[0x19, self.version()]);
+                result.extend_from_slice(domain_separator);
+                if let Some(message_hash) = message_hash {
+                    result.extend_from_slice(message_hash);
+                }
+                result
+            }
+            SignedData::PersonalMessage(message) => {
+                // the length is written as a decimal string
+                let length = message.len().to_string();
+
+                let mut result = Vec::with_capacity(
+                    PERSONAL_MESSAGE_PREFIX.len() + length.len() + message.len(),
+                );
+                result.extend_from_slice(PERSONAL_MESSAGE_PREFIX);
+                result.extend_from_slice(length.as_bytes());
+                result.extend_from_slice(message);
+                result
+            }
+        }
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedDataError {
+    /// the signature isn't 65 bytes or r or s are out of range
+    InvalidSignature,
+    Recovery(RecoveryError),
+}
+
+impl Display for SignedDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignedDataError::InvalidSignature => write!(f, "invalid signature"),
+            SignedDataError::Recovery(error) => write!(f, "can't recover the signer: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SignedDataError {}
+
+impl From<RecoveryError> for SignedDataError {
+    fn from(error: RecoveryError) -> Self {
+        SignedDataError::Recovery(error)
+    }
+}
+
+/// the hash of a `personal_sign` message (version 0x45)
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    SignedData::PersonalMessage(message).hash()
+}
+
+/// signs a message like `personal_sign`, returns the 65 byte r || s || v signature
+pub fn sign_message(private_key: &PrivateKey, message: &[u8]) -> Vec<u8> {
+    sign_data(private_key, &SignedData::PersonalMessage(message))
+}
+
+/// recovers the address that signed a message with `sign_message` or `personal_sign`
+pub fn recover_message_signer(
+    message: &[u8],
+    signature: &[u8],
+) -> Result<Address, SignedDataError> {
+    recover_data_signer(&SignedData::PersonalMessage(message), signature)
+}
+
+/// signs data of any EIP-191 version, returns the 65 byte r || s || v signature
+pub fn sign_data(private_key: &PrivateKey, data: &SignedData) -> Vec<u8> {
+    Signature::create(private_key, data.hash())
+        .to_signature_bytes()
+        .expect("signatures from `create` have a legacy v")
+}
+
+/// recovers the address that signed the data with `sign_data`
+pub fn recover_data_signer(
+    data: &SignedData,
+    signature: &[u8],
+) -> Result<Address, SignedDataError> {
+    // this also accepts the bare recovery id as v, which some hardware wallets return
+    let signature =
+        Signature::from_signature_bytes(signature).ok_or(SignedDataError::InvalidSignature)?;
+
+    Ok(signature.ecrecover(&data.hash())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn private_key() -> PrivateKey {
+        PrivateKey::from_hex("b25c7db31feed9122727bf0939dc769a96564b2de4c4726d035b36ecf1e5b364")
+            .unwrap()
+    }
+
+    #[test]
+    fn personal_message_hash() {
+        assert_eq!(
+            hex::encode(hash_message(b"Hello World")),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+        assert_eq!(
+            SignedData::PersonalMessage(b"abc").encode(),
+            b"\x19Ethereum Signed Message:\n3abc"
+        );
+        assert_eq!(
+            SignedData::PersonalMessage(&[0u8; 12]).encode()[..28],
+            b"\x19Ethereum Signed Message:\n12"[..]
+        );
+    }
+
+    #[test]
+    fn sign_message_vector() {
+        // from the web3.py documentation
+        let message = "I♥SF".as_bytes();
+        assert_eq!(
+            hex::encode(hash_message(message)),
+            "1476abb745d423bf09273f1afd887d951181d25adc66c4834a70491911b7f750"
+        );
+
+        let signature = sign_message(&private_key(), message);
+        assert_eq!(
+            hex::encode(&signature),
+            "e6ca9bba58c88611fad66a6ce8f996908195593807c4b38bd528d2cff09d4eb33e5bfbbf4d3e39b1a2fd816a7680c19ebebaf3a141b239934ad43cb33fcec8ce1c"
+        );
+        assert_eq!(
+            recover_message_signer(message, &signature),
+            Ok(private_key().public_key().address())
+        );
+    }
+
+    #[test]
+    fn recover_accepts_bare_recovery_ids() {
+        let mut signature = sign_message(&private_key(), b"hi");
+        signature[64] -= 27;
+
+        assert_eq!(
+            recover_message_signer(b"hi", &signature),
+            Ok(private_key().public_key().address())
+        );
+    }
+
+    #[test]
+    fn recover_errors() {
+        let signature = sign_message(&private_key(), b"hi");
+
+        assert_eq!(
+            recover_message_signer(b"hi", &signature[..64]),
+            Err(SignedDataError::InvalidSignature)
+        );
+        assert_eq!(
+            recover_message_signer(b"hi", &[0xff; 65]),
+            Err(SignedDataError::InvalidSignature)
+        );
+
+        // v is checked when parsing the signature
+        let mut invalid_v = signature.clone();
+        invalid_v[64] = 33;
+        assert_eq!(
+            recover_message_signer(b"hi", &invalid_v),
+            Err(SignedDataError::InvalidSignature)
+        );
+
+        // a different message recovers a different address
+        assert_ne!(
+            recover_message_signer(b"ho", &signature),
+            Ok(private_key().public_key().address())
+        );
+    }
+
+    #[test]
+    fn validator_data() {
+        let validator = private_key().public_key().address();
+        let data = SignedData::WithValidator {
+            validator: &validator,
+            data: b"payload",
+        };
+
+        let encoded = data.encode();
+        assert_eq!(data.version(), 0x00);
+        assert_eq!(encoded[..2], [0x19, 0x00]);
+        assert_eq!(encoded[2..22], validator.to_bytes());
+        assert_eq!(&encoded[22..], b"payload");
+        assert_eq!(data.hash(), keccak256(&encoded));
+
+        let signature = sign_data(&private_key(), &data);
+        assert_eq!(signature.len(), 65);
+        assert_eq!(
+            recover_data_signer(&data, &signature),
+            Ok(validator)
+        );
+
+        // the same bytes signed as a personal message don't recover the same signer
+        assert_ne!(
+            recover_message_signer(b"payload", &signature),
+            Ok(validator)
+        );
+    }
+}
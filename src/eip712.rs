@@ -0,0 +1,752 @@
+//! Typed structured data hashing and signing as described in EIP-712,
+//! compatible with `eth_signTypedData_v4`.
+//! https://eips.ethereum.org/EIPS/eip-712
+//!
+//! A struct is hashed as hashStruct(s) = keccak256(typeHash || encodeData(s)), where the type
+//! hash is the hash of its definition followed by the definitions of the structs it references.
+//! Every member is encoded as 32 bytes: atomic values are padded, `bytes` and `string` are
+//! hashed, structs are replaced by their hashStruct and arrays by the hash of their encoded
+//! elements. The hash that's signed is the EIP-191 version 0x01 hash of the domain separator
+//! (the hashStruct of the `EIP712Domain` struct) and the hashStruct of the message.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use num_bigint::{BigInt, Sign};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    address::Address,
+    ecdsa::PrivateKey,
+    eip191::{recover_data_signer, sign_data, SignedData, SignedDataError},
+    keccak::keccak256,
+    util::bigint_to_bytes32,
+};
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// a member of a struct type, like `{ "name": "wallet", "type": "address" }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Member {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// the JSON payload of `eth_signTypedData_v4`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    /// the struct types by name, including `EIP712Domain`
+    pub types: BTreeMap<String, Vec<Member>>,
+    pub primary_type: String,
+    pub domain: Value,
+    #[serde(default)]
+    pub message: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedDataError {
+    /// the payload isn't valid JSON or doesn't have the expected fields
+    Json(String),
+    /// a type is neither a struct in `types` nor an atomic, dynamic or array type
+    UnknownType(String),
+    /// a struct value doesn't have one of the members of its type
+    MissingMember {
+        ty: String,
+        member: String,
+    },
+    /// a value doesn't match its type, or doesn't fit in it
+    InvalidValue {
+        ty: String,
+        value: String,
+    },
+    Signature(SignedDataError),
+}
+
+impl Display for TypedDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypedDataError::Json(error) => write!(f, "invalid typed data: {}", error),
+            TypedDataError::UnknownType(ty) => write!(f, "unknown type {}", ty),
+            TypedDataError::MissingMember { ty, member } => {
+                write!(f, "{} value is missing the member {}", ty, member)
+            }
+            TypedDataError::InvalidValue { ty, value } => {
+                write!(f, "invalid {} value {}", ty, value)
+            }
+            TypedDataError::Signature(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TypedDataError {}
+
+impl From<SignedDataError> for TypedDataError {
+    fn from(error: SignedDataError) -> Self {
+        TypedDataError::Signature(error)
+    }
+}
+
+impl TypedData {
+    pub fn from_json(json: &str) -> Result<Self, TypedDataError> {
+        serde_json::from_str(json).map_err(|error| TypedDataError::Json(error.to_string()))
+    }
+
+    /// the definition of a struct type followed by the definitions of every struct it
+    /// references (directly or not) sorted by name, like `Mail(Person from,...)Person(...)`
+    pub fn encode_type(&self, name: &str) -> Result<String, TypedDataError> {
+        let mut dependencies = BTreeSet::new();
+        self.find_dependencies(name, &mut dependencies)?;
+        dependencies.remove(name);
+
+        let mut result = String::new();
+        for name in std::iter::once(name).chain(dependencies) {
+            let members: Vec<String> = self
+                .members(name)?
+                .iter()
+                .map(|member| format!("{} {}", member.ty, member.name))
+                .collect();
+
+            result.push_str(name);
+            result.push('(');
+            result.push_str(&members.join(","));
+            result.push(')');
+        }
+
+        Ok(result)
+    }
+
+    pub fn type_hash(&self, name: &str) -> Result<[u8; 32], TypedDataError> {
+        Ok(keccak256(self.encode_type(name)?.as_bytes()))
+    }
+
+    /// the 32 byte encodings of the members of a struct value, in the order of the type
+    pub fn encode_data(&self, name: &str, value: &Value) -> Result<Vec<u8>, TypedDataError> {
+        let members = self.members(name)?;
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid_value(name, value))?;
+
+        let mut result = Vec::with_capacity(32 * members.len());
+        for member in members {
+            let value = match object.get(&member.name) {
+                Some(value) => value,
+                // like MetaMask, missing structs are encoded the same as null ones
+                None if self.types.contains_key(&member.ty) => &Value::Null,
+                None => {
+                    return Err(TypedDataError::MissingMember {
+                        ty: name.to_string(),
+                        member: member.name.clone(),
+                    })
+                }
+            };
+
+            result.extend_from_slice(&self.encode_value(&member.ty, value)?);
+        }
+
+        Ok(result)
+    }
+
+    /// keccak256(typeHash || encodeData(value))
+    pub fn hash_struct(&self, name: &str, value: &Value) -> Result<[u8; 32], TypedDataError> {
+        let mut encoded = self.type_hash(name)?.to_vec();
+        encoded.extend_from_slice(&self.encode_data(name, value)?);
+
+        Ok(keccak256(&encoded))
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32], TypedDataError> {
+        self.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// the EIP-191 data that's signed, the message is left out if the primary type is the domain
+    pub fn signed_data(&self) -> Result<SignedData<'static>, TypedDataError> {
+        let message_hash = if self.primary_type == DOMAIN_TYPE {
+            None
+        } else {
+            Some(self.hash_struct(&self.primary_type, &self.message)?)
+        };
+
+        Ok(SignedData::Structured {
+            domain_separator: self.domain_separator()?,
+            message_hash,
+        })
+    }
+
+    /// the hash that's signed by `eth_signTypedData_v4`
+    pub fn hash(&self) -> Result<[u8; 32], TypedDataError> {
+        Ok(self.signed_data()?.hash())
+    }
+
+    /// signs like `eth_signTypedData_v4`, returns the 65 byte r || s || v signature
+    pub fn sign(&self, private_key: &PrivateKey) -> Result<Vec<u8>, TypedDataError> {
+        Ok(sign_data(private_key, &self.signed_data()?))
+    }
+
+    /// recovers the address that signed the data with `sign` or `eth_signTypedData_v4`
+    pub fn recover_signer(&self, signature: &[u8]) -> Result<Address, TypedDataError> {
+        Ok(recover_data_signer(&self.signed_data()?, signature)?)
+    }
+
+    fn members(&self, name: &str) -> Result<&[Member], TypedDataError> {
+        self.types
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| TypedDataError::UnknownType(name.to_string()))
+    }
+
+    /// adds the struct and every struct it references to `found`, checking all member types
+    fn find_dependencies<'a>(
+        &'a self,
+        name: &'a str,
+        found: &mut BTreeSet<&'a str>,
+    ) -> Result<(), TypedDataError> {
+        if found.contains(name) {
+            return Ok(());
+        }
+
+        let members = self.members(name)?;
+        found.insert(name);
+
+        for member in members {
+            let mut kind = Kind::parse(&member.ty, self)?;
+            while let Kind::Array(inner, _) = kind {
+                kind = Kind::parse(inner, self)?;
+            }
+
+            if let Kind::Struct(name) = kind {
+                self.find_dependencies(name, found)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode_value(&self, ty: &str, value: &Value) -> Result<[u8; 32], TypedDataError> {
+        let invalid = || invalid_value(ty, value);
+        let mut result = [0u8; 32];
+
+        match Kind::parse(ty, self)? {
+            Kind::Array(inner, length) => {
+                let items = value.as_array().ok_or_else(invalid)?;
+                if length.map_or(false, |length| length != items.len()) {
+                    return Err(invalid());
+                }
+
+                let mut encoded = Vec::with_capacity(32 * items.len());
+                for item in items {
+                    encoded.extend_from_slice(&self.encode_value(inner, item)?);
+                }
+                result = keccak256(&encoded);
+            }
+            Kind::Struct(name) => {
+                // null structs are encoded as zero, like MetaMask does
+                if !value.is_null() {
+                    result = self.hash_struct(name, value)?;
+                }
+            }
+            Kind::Bool => result[31] = value.as_bool().ok_or_else(invalid)? as u8,
+            Kind::Address => {
+                let bytes = parse_hex(value).filter(|bytes| bytes.len() == 20);
+                result[12..].copy_from_slice(&bytes.ok_or_else(invalid)?);
+            }
+            Kind::String => result = keccak256(value.as_str().ok_or_else(invalid)?.as_bytes()),
+            Kind::Bytes => result = keccak256(&parse_hex(value).ok_or_else(invalid)?),
+            Kind::FixedBytes(length) => {
+                let bytes = parse_hex(value).filter(|bytes| bytes.len() == length);
+                result[..length].copy_from_slice(&bytes.ok_or_else(invalid)?);
+            }
+            Kind::Uint(bits) => {
+                let n = parse_integer(value).ok_or_else(invalid)?;
+                result = encode_integer(&n, bits, false).ok_or_else(invalid)?;
+            }
+            Kind::Int(bits) => {
+                let n = parse_integer(value).ok_or_else(invalid)?;
+                result = encode_integer(&n, bits, true).ok_or_else(invalid)?;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// a member type, the number is the size in bits for integers and in bytes for `bytesN`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind<'a> {
+    /// `T[]` or `T[n]`
+    Array(&'a str, Option<usize>),
+    Struct(&'a str),
+    Bool,
+    Address,
+    String,
+    Bytes,
+    FixedBytes(usize),
+    Uint(usize),
+    Int(usize),
+}
+
+impl<'a> Kind<'a> {
+    fn parse(ty: &'a str, typed_data: &'a TypedData) -> Result<Self, TypedDataError> {
+        let unknown = || TypedDataError::UnknownType(ty.to_string());
+
+        if let Some(rest) = ty.strip_suffix(']') {
+            let (inner, length) = rest.rsplit_once('[').ok_or_else(unknown)?;
+            let length = match length {
+                "" => None,
+                length => Some(parse_size(length).ok_or_else(unknown)?),
+            };
+
+            return Ok(Kind::Array(inner, length));
+        }
+
+        let kind = match ty {
+            "bool" => Kind::Bool,
+            "address" => Kind::Address,
+            "string" => Kind::String,
+            "bytes" => Kind::Bytes,
+            _ if typed_data.types.contains_key(ty) => Kind::Struct(ty),
+            _ => if let Some(bytes) = ty.strip_prefix("bytes") {
+                parse_size(bytes)
+                    .filter(|bytes| (1..=32).contains(bytes))
+                    .map(Kind::FixedBytes)
+            } else if let Some(bits) = ty.strip_prefix("uint") {
+                parse_integer_size(bits).map(Kind::Uint)
+            } else if let Some(bits) = ty.strip_prefix("int") {
+                parse_integer_size(bits).map(Kind::Int)
+            } else {
+                None
+            }
+            .ok_or_else(unknown)?,
+        };
+
+        Ok(kind)
+    }
+}
+
+/// a decimal number without a sign or leading zeros
+fn parse_size(digits: &str) -> Option<usize> {
+    if digits.starts_with('0') || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+/// the size of `intN` and `uintN`, a multiple of 8 from 8 to 256
+fn parse_integer_size(bits: &str) -> Option<usize> {
+    parse_size(bits).filter(|bits| bits % 8 == 0 && (8..=256).contains(bits))
+}
+
+fn invalid_value(ty: &str, value: &Value) -> TypedDataError {
+    TypedDataError::InvalidValue {
+        ty: ty.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// a 0x prefixed hex string
+fn parse_hex(value: &Value) -> Option<Vec<u8>> {
+    let digits = value.as_str()?.strip_prefix("0x")?;
+    hex::decode(digits).ok()
+}
+
+/// a JSON number, or a decimal or 0x prefixed hex string for numbers that don't fit in 64 bits
+fn parse_integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| number.as_u64().map(BigInt::from)),
+        Value::String(string) => {
+            let (sign, digits) = match string.strip_prefix('-') {
+                Some(digits) => (Sign::Minus, digits),
+                None => (Sign::Plus, string.as_str()),
+            };
+
+            // only plain digits, `from_str_radix` would also accept signs and underscores
+            let (digits, radix) = match digits.strip_prefix("0x") {
+                Some(digits) if digits.bytes().all(|c| c.is_ascii_hexdigit()) => (digits, 16),
+                None if digits.bytes().all(|c| c.is_ascii_digit()) => (digits, 10),
+                _ => return None,
+            };
+            let magnitude = BigInt::parse_bytes(digits.as_bytes(), radix)?;
+
+            Some(if sign == Sign::Minus {
+                -magnitude
+            } else {
+                magnitude
+            })
+        }
+        _ => None,
+    }
+}
+
+/// a 256 bit two's complement big endian number, `None` if it doesn't fit in `bits` bits
+fn encode_integer(n: &BigInt, bits: usize, signed: bool) -> Option<[u8; 32]> {
+    let (min, max) = if signed {
+        let half: BigInt = BigInt::from(1u8) << (bits - 1);
+        (-half.clone(), half)
+    } else {
+        (BigInt::from(0u8), BigInt::from(1u8) << bits)
+    };
+
+    if *n < min || *n >= max {
+        return None;
+    }
+
+    if n.sign() == Sign::Minus {
+        Some(bigint_to_bytes32(&(n + (BigInt::from(1u8) << 256))))
+    } else {
+        Some(bigint_to_bytes32(n))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the example from EIP-712
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    // nested structs and arrays, null structs and integers at their limits
+    const ORDER: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "salt", "type": "bytes32" }
+            ],
+            "Order": [
+                { "name": "maker", "type": "Account" },
+                { "name": "legs", "type": "Leg[]" },
+                { "name": "matrix", "type": "int16[2][]" },
+                { "name": "tags", "type": "string[]" },
+                { "name": "data", "type": "bytes" },
+                { "name": "selector", "type": "bytes4" },
+                { "name": "nonce", "type": "uint64" },
+                { "name": "delta", "type": "int8" },
+                { "name": "amount", "type": "uint256" },
+                { "name": "active", "type": "bool" },
+                { "name": "referrer", "type": "Account" }
+            ],
+            "Account": [
+                { "name": "owner", "type": "address" },
+                { "name": "labels", "type": "bytes[]" }
+            ],
+            "Leg": [
+                { "name": "asset", "type": "address" },
+                { "name": "value", "type": "int256" },
+                { "name": "account", "type": "Account" }
+            ]
+        },
+        "primaryType": "Order",
+        "domain": {
+            "name": "Exchange",
+            "chainId": "0x89",
+            "salt": "0x0101010101010101010101010101010101010101010101010101010101010101"
+        },
+        "message": {
+            "maker": {
+                "owner": "0x1111111111111111111111111111111111111111",
+                "labels": ["0x", "0xdeadbeef"]
+            },
+            "legs": [
+                {
+                    "asset": "0x2222222222222222222222222222222222222222",
+                    "value": "-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+                    "account": { "owner": "0x3333333333333333333333333333333333333333", "labels": [] }
+                },
+                {
+                    "asset": "0x4444444444444444444444444444444444444444",
+                    "value": 42,
+                    "account": { "owner": "0x5555555555555555555555555555555555555555", "labels": ["0x00"] }
+                }
+            ],
+            "matrix": [[1, -1], [-32768, 32767]],
+            "tags": ["a", "ünïcode", ""],
+            "data": "0x0102030405",
+            "selector": "0xa9059cbb",
+            "nonce": "0xffffffffffffffff",
+            "delta": -128,
+            "amount": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            "active": true,
+            "referrer": null
+        }
+    }"#;
+
+    fn data_with_member(ty: &str, value: &str) -> TypedData {
+        TypedData::from_json(&format!(
+            r#"{{
+                "types": {{
+                    "EIP712Domain": [],
+                    "Test": [{{ "name": "value", "type": "{}" }}]
+                }},
+                "primaryType": "Test",
+                "domain": {{}},
+                "message": {{ "value": {} }}
+            }}"#,
+            ty, value
+        ))
+        .unwrap()
+    }
+
+    /// the hex encoding of a single member of type `ty`
+    fn encode(ty: &str, value: &str) -> Result<String, TypedDataError> {
+        let data = data_with_member(ty, value);
+        data.encode_data("Test", &data.message).map(hex::encode)
+    }
+
+    #[test]
+    fn mail_example() {
+        let data = TypedData::from_json(MAIL).unwrap();
+
+        assert_eq!(
+            data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(data.type_hash("Mail").unwrap()),
+            "a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"
+        );
+        assert_eq!(
+            hex::encode(data.domain_separator().unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(data.hash_struct("Mail", &data.message).unwrap()),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(data.hash().unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn mail_signature() {
+        let data = TypedData::from_json(MAIL).unwrap();
+        let private_key = PrivateKey::from_bytes(&keccak256(b"cow")).unwrap();
+
+        let signature = data.sign(&private_key).unwrap();
+        assert_eq!(
+            hex::encode(&signature),
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        );
+
+        let signer = data.recover_signer(&signature).unwrap();
+        assert_eq!(
+            String::from(signer),
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+        );
+        assert_eq!(
+            data.recover_signer(&signature[1..]),
+            Err(TypedDataError::Signature(SignedDataError::InvalidSignature))
+        );
+    }
+
+    #[test]
+    fn nested_arrays_and_integers() {
+        let data = TypedData::from_json(ORDER).unwrap();
+
+        assert_eq!(
+            data.encode_type("Order").unwrap(),
+            "Order(Account maker,Leg[] legs,int16[2][] matrix,string[] tags,bytes data,bytes4 selector,uint64 nonce,int8 delta,uint256 amount,bool active,Account referrer)Account(address owner,bytes[] labels)Leg(address asset,int256 value,Account account)"
+        );
+        assert_eq!(
+            hex::encode(data.domain_separator().unwrap()),
+            "d809c2c15e0bf73f1d07df117aee02f784bc374c6627a54c25ffa793dc8a3783"
+        );
+        assert_eq!(
+            hex::encode(data.hash_struct("Order", &data.message).unwrap()),
+            "15c71e431b5ec3dd102ff17c1f7161ebbc4848761f6826d58045e8b7c3ec3335"
+        );
+        assert_eq!(
+            hex::encode(data.hash().unwrap()),
+            "86ec26aaf8ba8a02c973f6971f9770041cf35a090a9aa0c6a361be4126e565ef"
+        );
+
+        let private_key = PrivateKey::random();
+        let signature = data.sign(&private_key).unwrap();
+        assert_eq!(
+            data.recover_signer(&signature),
+            Ok(private_key.public_key().address())
+        );
+    }
+
+    #[test]
+    fn domain_as_primary_type() {
+        let mut data = TypedData::from_json(MAIL).unwrap();
+        data.primary_type = DOMAIN_TYPE.to_string();
+        data.message = Value::Null;
+
+        let mut expected = vec![0x19, 0x01];
+        expected.extend_from_slice(&data.domain_separator().unwrap());
+        assert_eq!(data.hash().unwrap(), keccak256(&expected));
+    }
+
+    #[test]
+    fn integer_widths() {
+        for bits in (8..=256usize).step_by(8) {
+            let max: BigInt = (BigInt::from(1u8) << bits) - 1;
+            let half: BigInt = BigInt::from(1u8) << (bits - 1);
+
+            let uint = format!("uint{}", bits);
+            assert_eq!(
+                encode(&uint, &format!("\"{}\"", max)),
+                Ok(format!("{:0>64}", max.to_str_radix(16)))
+            );
+            assert!(encode(&uint, &format!("\"{}\"", &max + 1)).is_err());
+            assert!(encode(&uint, "-1").is_err());
+
+            let int = format!("int{}", bits);
+            assert_eq!(
+                encode(&int, &format!("\"-{}\"", half)),
+                Ok(format!(
+                    "{:0>64}",
+                    ((BigInt::from(1u8) << 256usize) - &half).to_str_radix(16)
+                ))
+            );
+            assert!(encode(&int, &format!("\"{}\"", half)).is_err());
+            assert!(encode(&int, &format!("\"-{}\"", &half + 1)).is_err());
+        }
+
+        assert_eq!(encode("int8", "-1"), Ok("ff".repeat(32)));
+        assert_eq!(
+            encode("uint16", "\"0x0100\""),
+            Ok(format!("{:0>64}", "100"))
+        );
+        assert_eq!(encode("uint16", "256"), encode("uint16", "\"256\""));
+
+        for invalid in [
+            "1.5",
+            "\"1_000\"",
+            "\"+1\"",
+            "\"--1\"",
+            "\"0x\"",
+            "\"\"",
+            "true",
+        ] {
+            assert!(encode("uint32", invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn atomic_values() {
+        assert_eq!(encode("bool", "true"), Ok(format!("{:0>64}", "1")));
+        assert_eq!(
+            encode("address", "\"0x00000000000000000000000000000000000000ff\""),
+            Ok(format!("{:0>64}", "ff"))
+        );
+        assert_eq!(
+            encode("bytes2", "\"0xabcd\""),
+            Ok(format!("{:0<64}", "abcd"))
+        );
+        assert_eq!(
+            encode("bytes", "\"0xabcd\""),
+            Ok(hex::encode(keccak256(&[0xab, 0xcd])))
+        );
+        assert_eq!(
+            encode("string", "\"abc\""),
+            Ok(hex::encode(keccak256(b"abc")))
+        );
+        assert_eq!(encode("uint8[]", "[]"), Ok(hex::encode(keccak256(&[]))));
+
+        let invalid = |ty: &str, value: &str| TypedDataError::InvalidValue {
+            ty: ty.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(encode("bool", "1"), Err(invalid("bool", "1")));
+        assert_eq!(
+            encode("address", "\"0x00\""),
+            Err(invalid("address", "\"0x00\""))
+        );
+        assert_eq!(
+            encode("bytes2", "\"0xab\""),
+            Err(invalid("bytes2", "\"0xab\""))
+        );
+        assert_eq!(
+            encode("bytes", "\"abcd\""),
+            Err(invalid("bytes", "\"abcd\""))
+        );
+        assert_eq!(encode("string", "null"), Err(invalid("string", "null")));
+        assert_eq!(encode("uint8[2]", "[1]"), Err(invalid("uint8[2]", "[1]")));
+    }
+
+    #[test]
+    fn invalid_types() {
+        for ty in [
+            "uint",
+            "uint7",
+            "uint264",
+            "uint08",
+            "int+8",
+            "bytes0",
+            "bytes33",
+            "Foo",
+            "uint8[",
+            "uint8[01]",
+        ] {
+            let data = data_with_member(ty, "0");
+            assert_eq!(
+                data.encode_type("Test"),
+                Err(TypedDataError::UnknownType(ty.to_string()))
+            );
+            assert!(data.hash().is_err());
+        }
+
+        let data = TypedData::from_json(MAIL).unwrap();
+        assert_eq!(
+            data.encode_type("Letter"),
+            Err(TypedDataError::UnknownType("Letter".to_string()))
+        );
+
+        let mut message = data.message.clone();
+        message.as_object_mut().unwrap().remove("contents");
+        assert_eq!(
+            data.hash_struct("Mail", &message),
+            Err(TypedDataError::MissingMember {
+                ty: "Mail".to_string(),
+                member: "contents".to_string()
+            })
+        );
+
+        assert!(matches!(
+            TypedData::from_json(r#"{ "types": {} }"#),
+            Err(TypedDataError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,737 @@
+//! Encrypted key files in the Web3 Secret Storage (keystore v3) format used by geth.
+//! https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/
+//!
+//! The password is stretched into a 32 byte key with scrypt or PBKDF2-HMAC-SHA256. The first
+//! half of it encrypts the private key with AES-128-CTR, the second half authenticates the
+//! ciphertext with mac = keccak256(key[16..32] || ciphertext), so a wrong password is detected
+//! before anything is decrypted.
+//!
+//! The scrypt parameters come from the file, so they are capped to keep a malicious file from
+//! exhausting memory. Like geth this doesn't enforce N < 2^(16 * r) from RFC 7914, the r = 1
+//! example of the definition breaks that rule.
+
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::Hmac;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::{
+    address::Address,
+    ecdsa::{PrivateKey, PrivateKeyError},
+    keccak::keccak256,
+};
+
+mod scrypt;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+const VERSION: u32 = 3;
+const CIPHER: &str = "aes-128-ctr";
+const PRF: &str = "hmac-sha256";
+const DERIVED_KEY_LENGTH: usize = 32;
+/// the most memory scrypt may use (128 * r * N bytes), geth's standard parameters take 256 MiB
+const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+/// the cap on p * r, the total work of scrypt grows with both
+const MAX_SCRYPT_PR: u64 = 1 << 8;
+
+/// how the password is turned into a key when encrypting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// scrypt with N = 2^log_n, files are only decrypted if 128 * r * N is at most 1 GiB
+    /// and p * r is at most 256, so encrypting with more fails
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+    Pbkdf2 {
+        iterations: u32,
+    },
+}
+
+impl Kdf {
+    /// geth's default, takes 256 MiB of memory and about a second
+    pub const STANDARD_SCRYPT: Kdf = Kdf::Scrypt {
+        log_n: 18,
+        r: 8,
+        p: 1,
+    };
+    /// geth's `--lightkdf`, for devices with little memory
+    pub const LIGHT_SCRYPT: Kdf = Kdf::Scrypt {
+        log_n: 12,
+        r: 8,
+        p: 6,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    /// the file isn't valid JSON or doesn't have the expected fields
+    Json(String),
+    UnsupportedVersion(u32),
+    UnsupportedCipher(String),
+    /// only scrypt and PBKDF2 with HMAC-SHA256 are supported
+    UnsupportedKdf(String),
+    /// the KDF parameters are out of range, too expensive or don't match the KDF
+    InvalidKdfParams,
+    /// a binary field isn't hex or has the wrong length
+    InvalidHex(&'static str),
+    /// the password is wrong or the file was modified
+    InvalidMac,
+    InvalidPrivateKey(PrivateKeyError),
+    /// the decrypted key doesn't belong to the address in the file
+    AddressMismatch,
+}
+
+impl Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeystoreError::Json(error) => write!(f, "invalid keystore file: {}", error),
+            KeystoreError::UnsupportedVersion(version) => {
+                write!(f, "unsupported keystore version {}", version)
+            }
+            KeystoreError::UnsupportedCipher(cipher) => write!(f, "unsupported cipher {}", cipher),
+            KeystoreError::UnsupportedKdf(kdf) => write!(f, "unsupported kdf {}", kdf),
+            KeystoreError::InvalidKdfParams => write!(f, "invalid kdf parameters"),
+            KeystoreError::InvalidHex(field) => write!(f, "invalid {}", field),
+            KeystoreError::InvalidMac => write!(f, "wrong password or corrupted file"),
+            KeystoreError::InvalidPrivateKey(error) => write!(f, "{}", error),
+            KeystoreError::AddressMismatch => write!(f, "key doesn't match the address"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<PrivateKeyError> for KeystoreError {
+    fn from(error: PrivateKeyError) -> Self {
+        KeystoreError::InvalidPrivateKey(error)
+    }
+}
+
+/// a keystore v3 file, binary fields are hex strings without the 0x prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    /// the address of the key, it's optional in the format but geth always writes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// some older tools wrote "Crypto"
+    #[serde(alias = "Crypto")]
+    pub crypto: Crypto,
+    pub id: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    Scrypt {
+        dklen: usize,
+        n: u64,
+        p: u32,
+        r: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: usize,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl Keystore {
+    /// encrypts a key with a random salt and iv, fails if the KDF parameters are out of range
+    pub fn encrypt(
+        private_key: &PrivateKey,
+        password: &str,
+        kdf: Kdf,
+    ) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        let mut id = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut iv);
+        OsRng.fill_bytes(&mut id);
+
+        Self::encrypt_with(private_key, password, kdf, &salt, &iv, &id)
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey, KeystoreError> {
+        if self.version != VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+
+        let crypto = &self.crypto;
+        if crypto.cipher != CIPHER {
+            return Err(KeystoreError::UnsupportedCipher(crypto.cipher.clone()));
+        }
+
+        let iv: [u8; 16] = decode_hex(&crypto.cipherparams.iv, "iv")?;
+        let mut key: [u8; 32] = decode_hex(&crypto.ciphertext, "ciphertext")?;
+        let mac: [u8; 32] = decode_hex(&crypto.mac, "mac")?;
+
+        let mut derived_key = derive_key(&crypto.kdf, &crypto.kdfparams, password)?;
+        let expected_mac = keccak256(&[&derived_key[16..32], &key[..]].concat());
+
+        // compare every byte so the time taken doesn't depend on where the MACs differ
+        let mac_matches = expected_mac
+            .iter()
+            .zip(mac.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+        if !mac_matches {
+            derived_key.zeroize();
+            return Err(KeystoreError::InvalidMac);
+        }
+
+        Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut key);
+        derived_key.zeroize();
+
+        let private_key = PrivateKey::from_bytes(&key);
+        key.zeroize();
+        let private_key = private_key?;
+
+        match self.address() {
+            Some(address) if address != private_key.public_key().address() => {
+                Err(KeystoreError::AddressMismatch)
+            }
+            _ => Ok(private_key),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        serde_json::from_str(json).map_err(|error| KeystoreError::Json(error.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("keystores can always be serialized")
+    }
+
+    /// the address in the file, `None` if it's missing or not valid hex
+    pub fn address(&self) -> Option<Address> {
+        let address = self.address.as_ref()?;
+        let bytes: [u8; 20] = decode_hex(address, "address").ok()?;
+
+        Some(Address::from(bytes))
+    }
+
+    /// the name geth gives the file in its keystore directory,
+    /// like `UTC--2023-04-01T12-30-00.000000000Z--<address>`, `None` for times before 1970
+    pub fn file_name(&self, created: SystemTime) -> Option<String> {
+        let since_epoch = created.duration_since(UNIX_EPOCH).ok()?;
+        let seconds = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days(seconds / 86400);
+
+        Some(format!(
+            "UTC--{:04}-{:02}-{:02}T{:02}-{:02}-{:02}.{:09}Z--{}",
+            year,
+            month,
+            day,
+            seconds / 3600 % 24,
+            seconds / 60 % 60,
+            seconds % 60,
+            since_epoch.subsec_nanos(),
+            self.address.as_deref().unwrap_or_default()
+        ))
+    }
+
+    fn encrypt_with(
+        private_key: &PrivateKey,
+        password: &str,
+        kdf: Kdf,
+        salt: &[u8; 32],
+        iv: &[u8; 16],
+        id: &[u8; 16],
+    ) -> Result<Self, KeystoreError> {
+        let salt = hex::encode(salt);
+        let kdfparams = match kdf {
+            Kdf::Scrypt { log_n, r, p } => KdfParams::Scrypt {
+                dklen: DERIVED_KEY_LENGTH,
+                n: 1u64
+                    .checked_shl(u32::from(log_n))
+                    .ok_or(KeystoreError::InvalidKdfParams)?,
+                p,
+                r,
+                salt,
+            },
+            Kdf::Pbkdf2 { iterations } => KdfParams::Pbkdf2 {
+                c: iterations,
+                dklen: DERIVED_KEY_LENGTH,
+                prf: PRF.to_string(),
+                salt,
+            },
+        };
+        let kdf_name = match kdfparams {
+            KdfParams::Scrypt { .. } => "scrypt",
+            KdfParams::Pbkdf2 { .. } => "pbkdf2",
+        };
+
+        let mut derived_key = derive_key(kdf_name, &kdfparams, password)?;
+
+        // encrypt straight from the key, so there's no plaintext copy to wipe
+        let mut ciphertext = [0u8; 32];
+        Aes128Ctr::new(derived_key[..16].into(), iv.into())
+            .apply_keystream_b2b(private_key.as_bytes(), &mut ciphertext)
+            .expect("the key and the ciphertext have the same length");
+        let mac = keccak256(&[&derived_key[16..32], &ciphertext[..]].concat());
+        derived_key.zeroize();
+
+        let address = private_key.public_key().address().to_bytes();
+
+        Ok(Self {
+            address: Some(hex::encode(address)),
+            crypto: Crypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams {
+                    iv: hex::encode(iv),
+                },
+                ciphertext: hex::encode(ciphertext),
+                kdf: kdf_name.to_string(),
+                kdfparams,
+                mac: hex::encode(mac),
+            },
+            id: format_uuid(id),
+            version: VERSION,
+        })
+    }
+}
+
+/// stretches the password with the KDF named `kdf`, the result is at least 32 bytes long
+fn derive_key(kdf: &str, params: &KdfParams, password: &str) -> Result<Vec<u8>, KeystoreError> {
+    match (kdf, params) {
+        (
+            "scrypt",
+            KdfParams::Scrypt {
+                dklen,
+                n,
+                p,
+                r,
+                salt,
+            },
+        ) => {
+            if !n.is_power_of_two() || !(DERIVED_KEY_LENGTH..=64).contains(dklen) {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+
+            let memory = n.checked_mul(128 * u64::from(*r));
+            if !matches!(memory, Some(memory) if memory <= MAX_SCRYPT_MEMORY)
+                || u64::from(*p) * u64::from(*r) > MAX_SCRYPT_PR
+            {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+
+            let salt = hex::decode(salt).map_err(|_| KeystoreError::InvalidHex("salt"))?;
+
+            let mut key = vec![0u8; *dklen];
+            scrypt::scrypt(
+                password.as_bytes(),
+                &salt,
+                n.trailing_zeros(),
+                *r,
+                *p,
+                &mut key,
+            )
+            .map_err(|_| KeystoreError::InvalidKdfParams)?;
+            Ok(key)
+        }
+        (
+            "pbkdf2",
+            KdfParams::Pbkdf2 {
+                c,
+                dklen,
+                prf,
+                salt,
+            },
+        ) => {
+            if prf != PRF {
+                return Err(KeystoreError::UnsupportedKdf(format!(
+                    "pbkdf2 with {}",
+                    prf
+                )));
+            }
+            if *c == 0 || !(DERIVED_KEY_LENGTH..=64).contains(dklen) {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+
+            let salt = hex::decode(salt).map_err(|_| KeystoreError::InvalidHex("salt"))?;
+
+            let mut key = vec![0u8; *dklen];
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), &salt, *c, &mut key);
+            Ok(key)
+        }
+        ("scrypt" | "pbkdf2", _) => Err(KeystoreError::InvalidKdfParams),
+        (kdf, _) => Err(KeystoreError::UnsupportedKdf(kdf.to_string())),
+    }
+}
+
+/// hex of exactly N bytes
+fn decode_hex<const N: usize>(hex: &str, field: &'static str) -> Result<[u8; N], KeystoreError> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(hex, &mut bytes).map_err(|_| KeystoreError::InvalidHex(field))?;
+    Ok(bytes)
+}
+
+/// a random (version 4) UUID
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let mut bytes = *bytes;
+    bytes[6] = bytes[6] & 0x0f | 0x40;
+    bytes[8] = bytes[8] & 0x3f | 0x80;
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex::encode(&bytes[..4]),
+        hex::encode(&bytes[4..6]),
+        hex::encode(&bytes[6..8]),
+        hex::encode(&bytes[8..10]),
+        hex::encode(&bytes[10..])
+    )
+}
+
+/// the (year, month, day) of a number of days since 1970-01-01
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    const PASSWORD: &str = "testpassword";
+    const PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    // the test vectors of the Web3 Secret Storage definition
+    const PBKDF2: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    const SCRYPT: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+            "ciphertext": "d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c",
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": 262144,
+                "r": 1,
+                "p": 8,
+                "salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+            },
+            "mac": "2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    // generated with Python's hashlib.scrypt (OpenSSL) and an independent keccak256
+    const SCRYPT_R8: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "101112131415161718191a1b1c1d1e1f" },
+            "ciphertext": "b2f0b7ccdba8068a3661fe42fe435e9d672e615aaaaad745b84a08fa1e741ee7",
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": 1024,
+                "r": 8,
+                "p": 1,
+                "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+            },
+            "mac": "07d323eed6af56b50142373e46e62d92e2beaa599dec2b7461926353d05b594f"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    /// cheap parameters, so the tests don't spend their time in the KDF
+    const TEST_SCRYPT: Kdf = Kdf::Scrypt {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    fn private_key() -> PrivateKey {
+        PrivateKey::from_hex(PRIVATE_KEY).unwrap()
+    }
+
+    #[test]
+    fn pbkdf2_vector() {
+        let keystore = Keystore::from_json(PBKDF2).unwrap();
+        assert_eq!(keystore.decrypt(PASSWORD), Ok(private_key()));
+    }
+
+    #[test]
+    fn scrypt_vector() {
+        // N = 2^18 with r = 1 is outside of the limits of RFC 7914, but geth accepts it
+        let keystore = Keystore::from_json(SCRYPT).unwrap();
+        assert_eq!(keystore.decrypt(PASSWORD), Ok(private_key()));
+
+        let keystore = Keystore::from_json(SCRYPT_R8).unwrap();
+        assert_eq!(keystore.decrypt(PASSWORD), Ok(private_key()));
+    }
+
+    #[test]
+    fn scrypt_limits() {
+        let keystore = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+        let with_params = |n: u64, r: u32, p: u32| {
+            let mut modified = keystore.clone();
+            modified.crypto.kdfparams = KdfParams::Scrypt {
+                dklen: 32,
+                n,
+                p,
+                r,
+                salt: "00".repeat(32),
+            };
+            modified.decrypt("")
+        };
+
+        // 2 GiB, twice the limit
+        assert_eq!(
+            with_params(1 << 21, 8, 1),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+        assert_eq!(
+            with_params(1 << 30, 8, 1),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+        assert_eq!(
+            with_params(1 << 63, 8, 1),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+        assert_eq!(
+            with_params(16, u32::MAX, 1),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+        assert_eq!(
+            with_params(16, 8, 1 << 20),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+
+        // encrypting checks the same limits instead of panicking
+        for (log_n, r, p) in [(64, 8, 1), (u8::MAX, 8, 1), (21, 8, 1), (4, 8, 1 << 20)] {
+            assert_eq!(
+                Keystore::encrypt(&private_key(), "", Kdf::Scrypt { log_n, r, p }),
+                Err(KeystoreError::InvalidKdfParams)
+            );
+        }
+        assert_eq!(
+            Keystore::encrypt(&private_key(), "", Kdf::Pbkdf2 { iterations: 0 }),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+    }
+
+    #[test]
+    fn encrypt_matches_vector() {
+        let keystore = Keystore::encrypt_with(
+            &private_key(),
+            PASSWORD,
+            Kdf::Pbkdf2 { iterations: 262144 },
+            &hex::decode("ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            &hex::decode("6087dab2f9fdbbfaddc31a909735c1e6")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            &[0; 16],
+        )
+        .unwrap();
+
+        let expected = Keystore::from_json(PBKDF2).unwrap();
+        assert_eq!(keystore.crypto, expected.crypto);
+        assert_eq!(
+            keystore.address.as_deref(),
+            Some("008aeeda4d805471df9b2a5b0f38a0c3bcba786b")
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        for kdf in [TEST_SCRYPT, Kdf::Pbkdf2 { iterations: 16 }] {
+            let keystore = Keystore::encrypt(&private_key(), "hunter2", kdf).unwrap();
+            let json = keystore.to_json();
+
+            let parsed = Keystore::from_json(&json).unwrap();
+            assert_eq!(parsed, keystore);
+            assert_eq!(parsed.decrypt("hunter2"), Ok(private_key()));
+            assert_eq!(parsed.decrypt("hunter3"), Err(KeystoreError::InvalidMac));
+        }
+
+        // the salt, iv and id are random
+        let first = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+        let second = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+        assert_ne!(first.crypto.ciphertext, second.crypto.ciphertext);
+        assert_ne!(first.id, second.id);
+    }
+
+    #[test]
+    fn geth_format() {
+        let keystore = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&keystore.to_json()).unwrap();
+
+        assert_eq!(json["address"], "008aeeda4d805471df9b2a5b0f38a0c3bcba786b");
+        assert_eq!(json["version"], 3);
+        assert_eq!(json["crypto"]["cipher"], "aes-128-ctr");
+        assert_eq!(json["crypto"]["kdf"], "scrypt");
+        assert_eq!(json["crypto"]["kdfparams"]["n"], 16);
+        assert_eq!(json["crypto"]["kdfparams"]["dklen"], 32);
+
+        // a version 4 UUID
+        let id = json["id"].as_str().unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert!("89ab".contains(&id[19..20]));
+
+        // older files use "Crypto"
+        let capitalized = keystore.to_json().replace("\"crypto\"", "\"Crypto\"");
+        assert_eq!(
+            Keystore::from_json(&capitalized).unwrap().decrypt(""),
+            Ok(private_key())
+        );
+    }
+
+    #[test]
+    fn file_names() {
+        let keystore = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+
+        assert_eq!(
+            keystore
+                .file_name(UNIX_EPOCH + Duration::new(1_680_352_200, 123))
+                .as_deref(),
+            Some("UTC--2023-04-01T12-30-00.000000123Z--008aeeda4d805471df9b2a5b0f38a0c3bcba786b")
+        );
+        assert_eq!(
+            keystore
+                .file_name(UNIX_EPOCH + Duration::from_secs(951_782_400))
+                .as_deref(),
+            Some("UTC--2000-02-29T00-00-00.000000000Z--008aeeda4d805471df9b2a5b0f38a0c3bcba786b")
+        );
+        assert!(keystore
+            .file_name(UNIX_EPOCH)
+            .unwrap()
+            .starts_with("UTC--1970-01-01T00-00-00.000000000Z--"));
+        assert_eq!(
+            keystore.file_name(UNIX_EPOCH - Duration::from_secs(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_files() {
+        let keystore = Keystore::encrypt(&private_key(), "", TEST_SCRYPT).unwrap();
+
+        let mut modified = keystore.clone();
+        modified.version = 1;
+        assert_eq!(
+            modified.decrypt(""),
+            Err(KeystoreError::UnsupportedVersion(1))
+        );
+
+        let mut modified = keystore.clone();
+        modified.crypto.cipher = "aes-128-cbc".to_string();
+        assert_eq!(
+            modified.decrypt(""),
+            Err(KeystoreError::UnsupportedCipher("aes-128-cbc".to_string()))
+        );
+
+        let mut modified = keystore.clone();
+        modified.crypto.kdf = "argon2".to_string();
+        assert_eq!(
+            modified.decrypt(""),
+            Err(KeystoreError::UnsupportedKdf("argon2".to_string()))
+        );
+
+        let mut modified = keystore.clone();
+        modified.crypto.kdf = "pbkdf2".to_string();
+        assert_eq!(modified.decrypt(""), Err(KeystoreError::InvalidKdfParams));
+
+        let mut modified = keystore.clone();
+        if let KdfParams::Scrypt { n, .. } = &mut modified.crypto.kdfparams {
+            *n = 17;
+        }
+        assert_eq!(modified.decrypt(""), Err(KeystoreError::InvalidKdfParams));
+
+        let mut modified = keystore.clone();
+        modified.crypto.ciphertext.pop();
+        assert_eq!(
+            modified.decrypt(""),
+            Err(KeystoreError::InvalidHex("ciphertext"))
+        );
+
+        let mut modified = keystore.clone();
+        let mut ciphertext = hex::decode(&keystore.crypto.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        modified.crypto.ciphertext = hex::encode(ciphertext);
+        assert_eq!(modified.decrypt(""), Err(KeystoreError::InvalidMac));
+
+        let mut modified = keystore;
+        modified.address = Some("00".repeat(20));
+        assert_eq!(modified.decrypt(""), Err(KeystoreError::AddressMismatch));
+
+        assert!(matches!(
+            Keystore::from_json(r#"{ "version": 3 }"#),
+            Err(KeystoreError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19448), (2023, 4, 1));
+    }
+}
@@ -0,0 +1,213 @@
+//! The scrypt key derivation function from RFC 7914.
+//! https://www.rfc-editor.org/rfc/rfc7914
+//!
+//! The RFC requires N < 2^(16 * r), but geth doesn't check that and the test vectors of the
+//! keystore format use N = 2^18 with r = 1, so this accepts any N that fits in memory.
+
+use hmac::Hmac;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// scrypt parameters that are out of range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidParams;
+
+/// fills `output` with the scrypt hash of the password, N = 2^log_n
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u32,
+    r: u32,
+    p: u32,
+    output: &mut [u8],
+) -> Result<(), InvalidParams> {
+    // the same limits as golang.org/x/crypto/scrypt, so the memory size can't overflow
+    if log_n == 0 || log_n >= usize::BITS - 1 || r == 0 || p == 0 {
+        return Err(InvalidParams);
+    }
+    if u64::from(r) * u64::from(p) >= 1 << 30 {
+        return Err(InvalidParams);
+    }
+    let n = 1usize << log_n;
+    let block_words = 32usize.checked_mul(r as usize).ok_or(InvalidParams)?;
+    n.checked_mul(block_words)
+        .and_then(|words| words.checked_mul(4))
+        .ok_or(InvalidParams)?;
+
+    let mut blocks = vec![0u8; block_words * 4 * p as usize];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password, salt, 1, &mut blocks);
+
+    let mut x = vec![0u32; block_words];
+    let mut v = vec![0u32; n * block_words];
+    for block in blocks.chunks_mut(block_words * 4) {
+        for (word, bytes) in x.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        ro_mix(&mut x, &mut v, n);
+
+        for (word, bytes) in x.iter().zip(block.chunks_mut(4)) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &blocks, 1, output);
+
+    blocks.zeroize();
+    x.zeroize();
+    v.zeroize();
+    Ok(())
+}
+
+/// scryptROMix, `v` has room for n copies of `x`
+fn ro_mix(x: &mut [u32], v: &mut [u32], n: usize) {
+    let block_words = x.len();
+    let mut scratch = vec![0u32; block_words];
+
+    for i in 0..n {
+        v[i * block_words..(i + 1) * block_words].copy_from_slice(x);
+        block_mix(x, &mut scratch);
+    }
+
+    for _ in 0..n {
+        // Integerify, the first 64 bits of the last 64 byte block, modulo n
+        let last = block_words - 16;
+        let j = (u64::from(x[last]) | u64::from(x[last + 1]) << 32) as usize & (n - 1);
+
+        for (x, v) in x.iter_mut().zip(&v[j * block_words..(j + 1) * block_words]) {
+            *x ^= v;
+        }
+        block_mix(x, &mut scratch);
+    }
+
+    scratch.zeroize();
+}
+
+/// scryptBlockMix with Salsa20/8, the even 64 byte blocks of the output come first
+fn block_mix(b: &mut [u32], scratch: &mut [u32]) {
+    let blocks = b.len() / 16;
+    let mut x: [u32; 16] = b[b.len() - 16..].try_into().unwrap();
+
+    for i in 0..blocks {
+        for (x, b) in x.iter_mut().zip(&b[i * 16..(i + 1) * 16]) {
+            *x ^= b;
+        }
+        salsa20_8(&mut x);
+
+        let position = if i % 2 == 0 {
+            i / 2
+        } else {
+            blocks / 2 + i / 2
+        };
+        scratch[position * 16..(position + 1) * 16].copy_from_slice(&x);
+    }
+
+    b.copy_from_slice(scratch);
+}
+
+/// the Salsa20 core with 8 rounds
+fn salsa20_8(block: &mut [u32; 16]) {
+    let mut x = *block;
+
+    macro_rules! quarter_round {
+        ($a:expr, $b:expr, $c:expr, $d:expr) => {
+            x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+            x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+            x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+            x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+        };
+    }
+
+    for _ in 0..4 {
+        // columns
+        quarter_round!(0, 4, 8, 12);
+        quarter_round!(5, 9, 13, 1);
+        quarter_round!(10, 14, 2, 6);
+        quarter_round!(15, 3, 7, 11);
+        // rows
+        quarter_round!(0, 1, 2, 3);
+        quarter_round!(5, 6, 7, 4);
+        quarter_round!(10, 11, 8, 9);
+        quarter_round!(15, 12, 13, 14);
+    }
+
+    for (block, x) in block.iter_mut().zip(x) {
+        *block = block.wrapping_add(x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(hex: &str) -> Vec<u32> {
+        hex::decode(hex)
+            .unwrap()
+            .chunks(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn salsa20_8_vector() {
+        // RFC 7914 section 8
+        let mut block: [u32; 16] = words(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1dee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e",
+        )
+        .try_into()
+        .unwrap();
+        salsa20_8(&mut block);
+
+        assert_eq!(
+            block.to_vec(),
+            words("a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81")
+        );
+    }
+
+    #[test]
+    fn block_mix_vector() {
+        // RFC 7914 section 9, r = 1
+        let mut b = words(
+            "f7ce0b653d2d72a4108cf5abe912ffdd777616dbbb27a70e8204f3ae2d0f6fad89f68f4811d1e87bcc3bd7400a9ffd29094f0184639574f39ae5a1315217bcd7894991447213bb226c25b54da86370fbcd984380374666bb8ffcb5bf40c254b067d27c51ce4ad5fed829c90b505a571b7f4d1cad6a523cda770e67bceaaf7e89",
+        );
+        let mut scratch = vec![0u32; b.len()];
+        block_mix(&mut b, &mut scratch);
+
+        assert_eq!(
+            b,
+            words("a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f8120edc975323881a80540f64c162dcd3c21077cfe5f8d5fe2b1a4168f953678b77d3b3d803b60e4ab920996e59b4d53b65d2a225877d5edf5842cb9f14eefe425")
+        );
+    }
+
+    #[test]
+    fn scrypt_vectors() {
+        // RFC 7914 section 12
+        let mut output = [0u8; 64];
+
+        scrypt(b"", b"", 4, 1, 1, &mut output).unwrap();
+        assert_eq!(
+            hex::encode(output),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+
+        scrypt(b"password", b"NaCl", 10, 8, 16, &mut output).unwrap();
+        assert_eq!(
+            hex::encode(output),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+    }
+
+    #[test]
+    fn invalid_params() {
+        let mut output = [0u8; 32];
+
+        assert_eq!(scrypt(b"", b"", 0, 1, 1, &mut output), Err(InvalidParams));
+        assert_eq!(scrypt(b"", b"", 4, 0, 1, &mut output), Err(InvalidParams));
+        assert_eq!(scrypt(b"", b"", 4, 1, 0, &mut output), Err(InvalidParams));
+        assert_eq!(
+            scrypt(b"", b"", 4, 1 << 15, 1 << 15, &mut output),
+            Err(InvalidParams)
+        );
+        assert_eq!(scrypt(b"", b"", 63, 1, 1, &mut output), Err(InvalidParams));
+    }
+}
@@ -1,28 +1,39 @@
 #![allow(dead_code)]
 
-use ecdsa::gen_random_private_key;
-
-use crate::ecdsa::{get_public_key, get_address};
+use crate::ecdsa::PrivateKey;
 
 mod address;
+mod bip32;
+mod bip39;
 mod ecdsa;
+mod ecies;
+mod eip191;
+mod eip712;
 mod keccak;
+mod keystore;
 mod trie;
+#[allow(clippy::len_zero, clippy::needless_return, clippy::redundant_clone)]
 mod rlp;
 mod util;
 
 fn main() {
-    let private_key = gen_random_private_key();
-    println!("Private key: {}", private_key);
+    let private_key = PrivateKey::random();
+    println!("Private key: 0x{}", private_key.to_hex());
 
-    let public_key = get_public_key(&private_key);
-    println!("Address: {}", get_address(&public_key));
+    let public_key = private_key.public_key();
+    println!("Address: {}", public_key.address());
 
     let message = "Hello, world!".as_bytes();
     let hash = keccak::keccak256(message);
     let signature = ecdsa::Signature::create(&private_key, hash);
 
-    println!("Signature: {}", hex::encode(signature.to_signature_bytes()));
+    println!(
+        "Signature: {}",
+        hex::encode(signature.to_signature_bytes().unwrap())
+    );
 
-    println!("Ecrecover: {}", signature.ecrecover(&hash));
+    match signature.ecrecover(&hash) {
+        Ok(address) => println!("Ecrecover: {}", address),
+        Err(error) => println!("Ecrecover failed: {}", error),
+    }
 }
@@ -5,3 +5,11 @@ pub fn bigint_from_hex(hex: &str) -> Result<BigInt, FromHexError> {
     let bytes = hex::decode(hex)?;
     Ok(BigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes))
 }
+
+/// big endian bytes of a non-negative number, left padded to 32 bytes
+pub fn bigint_to_bytes32(n: &BigInt) -> [u8; 32] {
+    let bytes = n.to_bytes_be().1;
+    let mut result = [0u8; 32];
+    result[32 - bytes.len()..].copy_from_slice(&bytes);
+    result
+}